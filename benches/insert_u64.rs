@@ -0,0 +1,40 @@
+//! Compares `insert(&x)` with the `insert_u64(x)` fast path.
+//!
+//! ```sh
+//! cargo bench --bench insert_u64
+//! ```
+
+use hyperloglockless::{AtomicHyperLogLog, HyperLogLog};
+use std::hint::black_box;
+use std::time::Instant;
+
+const N: u64 = 20_000_000;
+const PRECISION: u8 = 14;
+
+/// Runs `f` over `0..N` and prints the mean time per call.
+fn bench(name: &str, mut f: impl FnMut(u64)) {
+    let start = Instant::now();
+    for x in 0..N {
+        f(black_box(x));
+    }
+    let ns = start.elapsed().as_nanos() as f64 / N as f64;
+    println!("{:<32} {:>6.2} ns/op", name, ns);
+}
+
+fn main() {
+    let mut hll = HyperLogLog::seeded(PRECISION, 42);
+    bench("HyperLogLog::insert", |x| hll.insert(&x));
+    black_box(hll.count());
+
+    let mut hll = HyperLogLog::seeded(PRECISION, 42);
+    bench("HyperLogLog::insert_u64", |x| hll.insert_u64(x));
+    black_box(hll.count());
+
+    let hll = AtomicHyperLogLog::seeded(PRECISION, 42);
+    bench("AtomicHyperLogLog::insert", |x| hll.insert(&x));
+    black_box(hll.count());
+
+    let hll = AtomicHyperLogLog::seeded(PRECISION, 42);
+    bench("AtomicHyperLogLog::insert_u64", |x| hll.insert_u64(x));
+    black_box(hll.count());
+}
@@ -0,0 +1,139 @@
+//! Generates `RAW_ESTIMATE` and `BIAS` in `src/bias_tables.rs`.
+//!
+//! For each precision `p` in `4..=18` and each `n = floor(k * m / 10)`, `k` in
+//! `0..=50`, the raw estimate `α * m^2 / sum(2^-register)` is averaged over
+//! `2^31 / (5 * m)` (at least 1000) sketches, and the bias is that mean minus
+//! `n`. Hashes are `splitmix64` of a counter seeded with the precision and the
+//! index of the sketch, so the output is deterministic.
+//!
+//! ```sh
+//! cargo run --release --example bias_tables
+//! ```
+
+/// The `splitmix64` finalizer, used as a deterministic source of uniformly
+/// random hashes.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// `α` for `m` registers, the same as `correction` in `src/lib.rs`.
+fn correction(m: usize) -> f64 {
+    let base = 0.7213475204444817;
+    let approx = 1.0794415416798357;
+    match m {
+        16 => 0.673,
+        32 => 0.697,
+        64 => 0.709,
+        _ => base / (1.0 + approx / m as f64),
+    }
+}
+
+const POINTS: usize = 51;
+
+/// Returns the mean raw estimate at each `n = floor(k * m / 10)`.
+fn mean_raw_estimates(p: u32) -> [f64; POINTS] {
+    let m = 1usize << p;
+    let alpha = correction(m);
+    let trials = ((1usize << 31) / (5 * m)).max(1000);
+    let mut acc = [0.0; POINTS];
+    let mut registers = vec![0u8; m];
+    for trial in 0..trials {
+        registers.iter_mut().for_each(|r| *r = 0);
+        let mut sum = m as f64;
+        let mut counter = ((p as u64) << 56) ^ ((trial as u64) << 28);
+        let mut n = 0;
+        for (k, acc) in acc.iter_mut().enumerate() {
+            while n < k * m / 10 {
+                let hash = splitmix64(counter);
+                counter = counter.wrapping_add(1);
+                let index = (hash >> (64 - p)) as usize;
+                let new = 1 + hash.trailing_zeros().min(63 - p) as u8;
+                let old = registers[index];
+                if new > old {
+                    registers[index] = new;
+                    sum += 2f64.powi(-(new as i32)) - 2f64.powi(-(old as i32));
+                }
+                n += 1;
+            }
+            *acc += alpha * (m * m) as f64 / sum;
+        }
+    }
+    acc.map(|x| x / trials as f64)
+}
+
+/// Formats `x` with 6 significant digits, like C's `%g`, as a float literal.
+/// `x` is rounded to 7 significant digits first, as the tables were.
+fn format_float(x: f64) -> String {
+    let x: f64 = format!("{:.6e}", x).parse().unwrap();
+    let sci = format!("{:.5e}", x);
+    let (mantissa, exp) = sci.split_once('e').unwrap();
+    let exp: i32 = exp.parse().unwrap();
+    if (-4..6).contains(&exp) {
+        let fixed = format!("{:.*}", (5 - exp) as usize, x);
+        let fixed = match fixed.contains('.') {
+            true => fixed.trim_end_matches('0').trim_end_matches('.'),
+            false => &fixed,
+        };
+        match fixed.contains('.') {
+            true => fixed.to_string(),
+            false => format!("{}.0", fixed),
+        }
+    } else {
+        let mantissa = mantissa.trim_end_matches('0').trim_end_matches('.');
+        format!("{}e{}", mantissa, exp)
+    }
+}
+
+/// Prints `rows` as the body of a `[[f64; POINTS]; 15]` constant, wrapping
+/// lines at 100 columns.
+fn print_table(name: &str, doc: &str, rows: &[[f64; POINTS]]) {
+    println!("/// {}", doc);
+    println!("#[rustfmt::skip]");
+    println!("pub(crate) const {}: [[f64; POINTS]; 15] = [", name);
+    for (p, row) in (4..).zip(rows) {
+        println!("    // p = {}", p);
+        println!("    [");
+        let mut line = String::from("       ");
+        for x in row {
+            let item = format!(" {},", format_float(*x));
+            if line.len() + item.len() > 100 {
+                println!("{}", line);
+                line = String::from("       ");
+            }
+            line.push_str(&item);
+        }
+        println!("{}", line);
+        println!("    ],");
+    }
+    println!("];");
+}
+
+fn main() {
+    let mut raw_estimates = Vec::new();
+    let mut biases = Vec::new();
+    for p in 4..=18 {
+        let raw = mean_raw_estimates(p);
+        let m = 1 << p;
+        let mut bias = raw;
+        for (k, b) in bias.iter_mut().enumerate() {
+            *b -= (k * m / 10) as f64;
+        }
+        raw_estimates.push(raw);
+        biases.push(bias);
+        eprintln!("p = {} done", p);
+    }
+    print_table(
+        "RAW_ESTIMATE",
+        "Mean raw estimates, increasing, for precisions 4..=18.",
+        &raw_estimates,
+    );
+    println!();
+    print_table(
+        "BIAS",
+        "Mean bias of the raw estimates in [`RAW_ESTIMATE`], for precisions 4..=18.",
+        &biases,
+    );
+}
@@ -23,6 +23,11 @@ impl AtomicF64 {
         f64::from_bits(self.0.load(ordering))
     }
 
+    #[inline]
+    pub fn store(&self, float: f64, ordering: Ordering) {
+        self.0.store(float.to_bits(), ordering)
+    }
+
     #[inline]
     pub fn fetch_sub(&self, val: f64, ordering: Ordering) -> f64 {
         let int = self
@@ -20,19 +20,35 @@ pub(crate) fn beta_horner(zeros: usize, precision: u32) -> f64 {
     res * zl + beta[0] * zeros_f
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_zero() {
-        for p in 4..=18 {
-            assert_eq!(0.0, beta_horner(0, p));
-        }
-    }
+/// Returns the LogLog-Beta bias correction term `β(z)` that this crate adds to
+/// the harmonic sum of the registers before computing a count.
+///
+/// `β(z) = b0 * z + b1 * zl + b2 * zl^2 + ... + b7 * zl^7`, where `z` is the
+/// number of zero registers, `zl = ln(z + 1)`, and `b0..=b7` are coefficients
+/// fitted per precision (see the LogLog-Beta paper,
+/// <https://arxiv.org/pdf/1612.02284.pdf>). A count matching this crate's is
+/// then `α * m * (m - z) / (sum(2^-register) + β(z))` for `m = 1 << precision`
+/// registers.
+///
+/// `zeros` should be in `0..=(1 << precision)`; `β(0) = 0`.
+///
+/// # Panics
+/// Panics if `precision` is not in `4..=18`.
+///
+/// # Example
+/// ```
+/// use hyperloglockless::beta_correction;
+///
+/// assert_eq!(beta_correction(0, 12), 0.0);
+/// ```
+pub fn beta_correction(zeros: usize, precision: u32) -> f64 {
+    let valid = u8::try_from(precision).is_ok_and(|p| crate::PRECISION_RANGE.contains(&p));
+    assert!(valid, "Precisions 4..=18 supported only.");
+    beta_horner(zeros, precision)
 }
 
 /// LogLog-Beta polynomial coefficients for precision in [4..18] range.
+#[allow(clippy::excessive_precision)]
 const BETA: [[f64; 8]; 15] = [
     // p = 4
     [
@@ -119,7 +135,7 @@ const BETA: [[f64; 8]; 15] = [
         -1.65687801845180e-02,
         -7.95829341087617e-02,
         4.71830602102918e-02,
-        -7.81372902346934e03,
+        -7.81372902346934e-03,
         5.84268708489995e-04,
     ],
     // p = 12
@@ -200,3 +216,49 @@ const BETA: [[f64; 8]; 15] = [
         0.00291076804642205,
     ],
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero() {
+        for p in 4..=18 {
+            assert_eq!(0.0, beta_horner(0, p));
+        }
+    }
+
+    #[test]
+    fn test_beta_correction() {
+        for p in 4..=18 {
+            for zeros in [0, 1, 1 << (p - 1), 1 << p] {
+                assert_eq!(beta_correction(zeros, p), beta_horner(zeros, p));
+            }
+        }
+    }
+
+    /// A wrong coefficient makes the estimate collapse at some fill levels,
+    /// e.g. to about 0 for 1000 elements at precision 11.
+    #[test]
+    fn test_estimate_every_precision() {
+        let mut rng = fastrand::Rng::with_seed(42);
+        for p in 4..=18u8 {
+            let m = 1usize << p;
+            for n in [m / 4, m / 2, m, 4 * m] {
+                let mut hll = crate::HyperLogLog::seeded(p, 42);
+                for _ in 0..n {
+                    hll.insert_hash(rng.u64(..));
+                }
+                let err = (hll.estimate() - n as f64).abs() / n as f64;
+                let bound = 5.0 * 1.04 / (m as f64).sqrt();
+                assert!(err < bound, "p={} n={} estimate={}", p, n, hll.estimate());
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_beta_correction_invalid_precision() {
+        beta_correction(1, 19);
+    }
+}
@@ -22,10 +22,16 @@ impl Buf {
         self.len
     }
 
+    /// Returns the number of bytes allocated for the buffer.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
     #[cold]
     fn resize(&mut self) {
         assert!(self.max_len > self.buf.len());
-        let new_cap = core::cmp::min(1 + (3 * self.buf.len()) >> 1, self.max_len);
+        let new_cap = core::cmp::min((1 + (3 * self.buf.len())) >> 1, self.max_len);
         self.buf.resize(new_cap + 8, 0);
     }
 
@@ -39,6 +45,40 @@ impl Buf {
         self.len += num;
     }
 
+    /// Overwrites the `num` bytes at `index` with the low `num` bytes of `x`,
+    /// like [`Self::push`] but at `index` instead of the end. Unlike `push`,
+    /// the bytes after them are left untouched, so a value can be patched in
+    /// the middle of the buffer.
+    ///
+    /// # Panics
+    /// Panics if `num > 8` or `index + num > self.len()`: only bytes already
+    /// written can be overwritten.
+    #[allow(dead_code)]
+    #[inline]
+    pub fn write_at(&mut self, index: usize, x: u64, num: usize) {
+        assert!(num <= 8 && index + num <= self.len, "write out of bounds");
+        self.buf[index..index + num].copy_from_slice(&x.to_le_bytes()[..num]);
+    }
+
+    /// Appends the bytes of `data`, growing the buffer as needed.
+    #[allow(dead_code)]
+    #[inline]
+    pub fn extend_from_slice(&mut self, data: &[u8]) {
+        let l = self.len();
+        while l + data.len() + 8 > self.buf.len() {
+            self.resize();
+        }
+        self.buf[l..l + data.len()].copy_from_slice(data);
+        self.len += data.len();
+    }
+
+    /// Returns the written bytes.
+    #[allow(dead_code)]
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
     #[inline]
     pub fn read_u64(&self, i: usize) -> u64 {
         debug_assert!(self.buf[i..].len() >= 8);
@@ -57,7 +97,49 @@ impl PartialEq for Buf {
         if self.len() != other.len() {
             return false;
         }
-        self.buf == self.buf
+        self.buf[..self.len] == other.buf[..other.len]
     }
 }
 impl Eq for Buf {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extend_from_slice() {
+        let mut buf = Buf::new(4, 100);
+        buf.push(u64::MAX, 3);
+        buf.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        assert_eq!(buf.len(), 13);
+        assert_eq!(buf.read_u64(0) & 0xFFFFFF, 0xFFFFFF);
+        assert_eq!(buf.read_u64(3).to_le_bytes(), [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(buf.read_u64(5).to_le_bytes()[..6], [3, 4, 5, 6, 7, 8]);
+
+        let mut other = Buf::new(0, 100);
+        other.extend_from_slice(&[255, 255, 255, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        assert_eq!(buf, other);
+    }
+
+    #[test]
+    fn test_write_at() {
+        let mut buf = Buf::new(4, 100);
+        buf.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        buf.write_at(2, 0xBBAA, 2);
+        assert_eq!(buf.as_slice(), [1, 2, 0xAA, 0xBB, 5, 6, 7, 8, 9, 10]);
+        buf.write_at(0, u64::MAX, 0);
+        buf.write_at(2, 0x0807060504030201, 8);
+        assert_eq!(buf.as_slice(), [1, 2, 1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(buf.len(), 10);
+        buf.write_at(9, 0xFF, 1);
+        assert_eq!(buf.as_slice()[9], 0xFF);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_write_at_out_of_bounds() {
+        let mut buf = Buf::new(100, 100);
+        buf.push(42, 1);
+        buf.write_at(0, 42, 2);
+    }
+}
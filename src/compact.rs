@@ -0,0 +1,273 @@
+use crate::{beta_horner, correction, hash_one, validate_precision, DefaultHasher, Error, INV_POW2};
+use alloc::{boxed::Box, vec::Vec};
+use core::hash::{BuildHasher, Hash};
+use core::iter::repeat;
+
+/// The largest value a 4-bit register can hold.
+const MAX_REGISTER: u8 = 15;
+
+/// A [`crate::HyperLogLog`] with 4-bit registers, using half the memory:
+/// `1 << (precision - 1)` bytes.
+///
+/// Registers saturate at 15, i.e. hashes with 14 or more trailing zeros all
+/// produce a register value of 15. The count is as accurate as [`crate::HyperLogLog`]
+/// while few registers are saturated, which holds while the count is well
+/// below `2^14 * (1 << precision)`, e.g. roughly 10 million at precision 10.
+/// Past that point, the count is underestimated. This makes it most
+/// appropriate for precisions up to 12 and cardinalities that are not
+/// extremely large.
+///
+/// # Example
+/// ```rust
+/// use hyperloglockless::Compact4BitHyperLogLog;
+///
+/// let mut hll = Compact4BitHyperLogLog::new(12);
+/// hll.insert("42");
+/// hll.insert("🦀");
+///
+/// let count = hll.count();
+/// let registers_bytes = hll.memory_usage_bytes() - core::mem::size_of_val(&hll);
+/// assert_eq!(registers_bytes, 1 << 11);
+/// ```
+#[derive(Clone)]
+#[cfg_attr(feature = "verbose_debug", derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Compact4BitHyperLogLog<S = DefaultHasher> {
+    /// Register `i` is the high nibble of `registers[i / 2]` if `i` is even
+    /// and the low nibble if odd.
+    registers: Box<[u8]>,
+    /// `2 * registers.len() == 1 << precision`
+    precision: u32,
+    hasher: S,
+    zeros: usize,
+    sum: f64,
+    correction: f64,
+}
+
+impl Compact4BitHyperLogLog {
+    /// Returns a new [`Self`] with `1 << precision` registers (4 bits each)
+    /// using the default hasher with a random seed.
+    pub fn new(precision: u8) -> Self {
+        Self::with_hasher(precision, DefaultHasher::default())
+    }
+
+    /// Returns a new [`Self`] with `1 << precision` registers (4 bits each)
+    /// using the default hasher seeded with `seed`.
+    pub fn seeded(precision: u8, seed: u128) -> Self {
+        Self::with_hasher(precision, DefaultHasher::seeded(&seed.to_be_bytes()))
+    }
+}
+
+impl<S: BuildHasher> Compact4BitHyperLogLog<S> {
+    /// Returns a new [`Self`] with `1 << precision` registers (4 bits each)
+    /// using the provided hasher.
+    pub fn with_hasher(precision: u8, hasher: S) -> Self {
+        validate_precision(precision);
+        let num_registers = 1usize << precision;
+        let registers: Vec<_> = repeat(0).take(num_registers >> 1).collect();
+        Self {
+            hasher,
+            precision: precision as u32,
+            zeros: num_registers,
+            correction: correction(num_registers),
+            registers: registers.into(),
+            sum: f64::from(num_registers as u32),
+        }
+    }
+
+    /// Returns the number registers in `self`.
+    #[allow(clippy::len_without_is_empty)]
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.registers.len() << 1
+    }
+
+    /// Returns the precision of `self`.
+    #[inline]
+    pub fn precision(&self) -> u8 {
+        self.precision as u8
+    }
+
+    /// Returns the number of bytes used by `self`, including the registers
+    /// on the heap.
+    #[inline]
+    pub fn memory_usage_bytes(&self) -> usize {
+        core::mem::size_of::<Self>() + self.registers.len()
+    }
+
+    #[inline(always)]
+    fn get(&self, index: usize) -> u8 {
+        let byte = self.registers[index >> 1];
+        match index & 1 {
+            0 => byte >> 4,
+            _ => byte & 0xF,
+        }
+    }
+
+    #[inline(always)]
+    fn set(&mut self, index: usize, value: u8) {
+        let byte = &mut self.registers[index >> 1];
+        *byte = match index & 1 {
+            0 => (*byte & 0xF) | (value << 4),
+            _ => (*byte & 0xF0) | value,
+        };
+    }
+
+    /// Returns an iterator over the value of each register.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        self.registers.iter().flat_map(|b| [b >> 4, b & 0xF])
+    }
+
+    #[inline(always)]
+    fn update(&mut self, new: u8, index: usize) {
+        let old = self.get(index);
+        if new > old {
+            self.set(index, new);
+            self.zeros -= (old == 0) as usize;
+            self.sum -= INV_POW2[old as usize] - INV_POW2[new as usize];
+        }
+    }
+
+    /// Inserts the item into the HyperLogLog.
+    #[inline]
+    pub fn insert<T: Hash + ?Sized>(&mut self, value: &T) {
+        self.insert_hash(hash_one(&self.hasher, value));
+    }
+
+    /// Inserts the hash of an item into the HyperLogLog.
+    #[inline]
+    pub fn insert_hash(&mut self, hash: u64) {
+        let index = (hash >> (64 - self.precision)) as usize;
+        let new = core::cmp::min(1 + hash.trailing_zeros() as u8, MAX_REGISTER);
+        self.update(new, index);
+    }
+
+    /// Returns the approximate number of elements in `self`.
+    #[inline]
+    pub fn count(&self) -> usize {
+        crate::math::round(self.estimate()) as usize
+    }
+
+    /// Returns the approximate number of elements in `self` as a float.
+    /// [`Self::count`] is this estimate rounded to an integer.
+    #[inline]
+    pub fn estimate(&self) -> f64 {
+        let d = self.sum + beta_horner(self.zeros, self.precision);
+        self.correction * (self.len() * (self.len() - self.zeros)) as f64 / d
+    }
+
+    /// Returns the approximate number of elements in `self` as a float.
+    #[deprecated(note = "renamed to `estimate`")]
+    #[inline]
+    pub fn raw_count(&self) -> f64 {
+        self.estimate()
+    }
+
+    /// Merges another HyperLogLog into `self`, updating the count.
+    /// Returns `Err(Error::IncompatibleLength)` if the two HyperLogLogs have
+    /// different length ([`Self::len`]).
+    ///
+    /// This does not verify that the HLLs use the same hasher or seed.
+    /// If they are different then `self` will be "corrupted".
+    pub fn union(&mut self, other: &Self) -> Result<(), Error> {
+        if self.len() != other.len() {
+            return Err(Error::IncompatibleLength);
+        }
+        other.iter().enumerate().for_each(|(i, x)| self.update(x, i));
+        Ok(())
+    }
+}
+
+impl<T: Hash, S: BuildHasher> Extend<T> for Compact4BitHyperLogLog<S> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+/// Shows a summary of `self` instead of every register value. Enable the
+/// `verbose_debug` feature for the full register dump.
+#[cfg(not(feature = "verbose_debug"))]
+impl<S: BuildHasher + core::fmt::Debug> core::fmt::Debug for Compact4BitHyperLogLog<S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Compact4BitHyperLogLog")
+            .field("precision", &self.precision)
+            .field("count", &format_args!("~{}", self.count()))
+            .field("fill_factor", &((self.len() - self.zeros) as f64 / self.len() as f64))
+            .field("hasher", &self.hasher)
+            .finish()
+    }
+}
+
+impl<S: BuildHasher> PartialEq for Compact4BitHyperLogLog<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.registers == other.registers
+    }
+}
+impl<S: BuildHasher> Eq for Compact4BitHyperLogLog<S> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HyperLogLog;
+
+    #[test]
+    fn test_matches_hyperloglog() {
+        for precision in 4..=18 {
+            let mut compact = Compact4BitHyperLogLog::seeded(precision, 42);
+            let mut hll = HyperLogLog::seeded(precision, 42);
+            for x in 0..10_000 {
+                compact.insert(&x);
+                hll.insert(&x);
+            }
+            assert_eq!(compact.len(), hll.len());
+            let clamped = hll.iter().map(|r| core::cmp::min(r, MAX_REGISTER));
+            assert!(compact.iter().eq(clamped));
+            if hll.iter().all(|r| r < MAX_REGISTER) {
+                assert_eq!(compact.estimate(), hll.estimate());
+            }
+        }
+    }
+
+    #[test]
+    fn test_saturation() {
+        let mut compact = Compact4BitHyperLogLog::seeded(4, 42);
+        compact.insert_hash(0);
+        compact.insert_hash(1 << 30);
+        assert_eq!(compact.iter().next(), Some(MAX_REGISTER));
+        assert_eq!(compact.iter().nth(1), Some(0));
+    }
+
+    #[cfg(not(feature = "verbose_debug"))]
+    #[test]
+    fn test_debug() {
+        let mut hll = Compact4BitHyperLogLog::seeded(4, 42);
+        hll.insert(&1);
+        let s = alloc::format!("{:?}", hll);
+        let prefix = "Compact4BitHyperLogLog { precision: 4, count: ~1, fill_factor: 0.0625, hasher: ";
+        assert!(s.starts_with(prefix), "{}", s);
+    }
+
+    #[test]
+    fn test_union() {
+        for precision in [4, 10, 16] {
+            let mut left = Compact4BitHyperLogLog::seeded(precision, 42);
+            let mut right = Compact4BitHyperLogLog::seeded(precision, 42);
+            let mut control = Compact4BitHyperLogLog::seeded(precision, 42);
+            left.extend(0..1000);
+            right.extend(500..2000);
+            control.extend(0..2000);
+            left.union(&right).unwrap();
+            assert_eq!(left, control);
+            assert_eq!(left.estimate(), control.estimate());
+        }
+        let mut hll = Compact4BitHyperLogLog::seeded(4, 42);
+        assert_eq!(
+            hll.union(&Compact4BitHyperLogLog::seeded(5, 42)),
+            Err(Error::IncompatibleLength)
+        );
+    }
+}
@@ -1,4 +1,50 @@
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum Error {
+    /// The HyperLogLogs have a different number of registers.
     IncompatibleLength,
+    /// The precision is not supported or not valid for the operation.
+    InvalidPrecision,
+    /// The bytes are not a valid encoding.
+    InvalidEncoding,
+    /// The encoding has a format version this version of the crate can't read.
+    UnsupportedVersion {
+        /// The format version of the encoding.
+        version: u32,
+    },
+    /// An index or range of registers is out of bounds.
+    IndexOutOfBounds,
+    /// The operation needs at least one HyperLogLog, but none were given.
+    Empty,
+    /// The number of imported registers is not the number required.
+    InvalidRegisterCount {
+        /// The number of registers required.
+        expected: usize,
+        /// The number of registers provided.
+        got: usize,
+    },
+    /// The HyperLogLog was modified concurrently with the operation.
+    ConcurrentModification,
+    /// The string is not valid hex.
+    HexDecodeError {
+        /// The index of the first invalid character, or the length of the
+        /// string if it has an odd number of characters.
+        index: usize,
+    },
+    /// The string is not valid base64.
+    #[cfg(feature = "base64")]
+    Base64DecodeError(base64::DecodeError),
+}
+
+/// Describes why two HyperLogLogs cannot be merged or compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompatibilityError {
+    /// The precision of the HyperLogLog the check was called on.
+    pub self_precision: u8,
+    /// The precision of the other HyperLogLog.
+    pub other_precision: u8,
+    /// The number of registers of the HyperLogLog the check was called on.
+    pub self_len: usize,
+    /// The number of registers of the other HyperLogLog.
+    pub other_len: usize,
 }
@@ -29,6 +29,17 @@ impl<H: Hasher + Clone> BuildHasher for CloneBuildHasher<H> {
 /// `std::collections::hash_map::RandomState` or `SipHasher13`.
 /// This is important because `build_hasher` is called once for every actual
 /// hash.
+///
+/// # Stability
+/// `DefaultHasher` is SipHash-1-3 keyed with the 128-bit seed (see
+/// [`crate::HyperLogLog::seeded`]). For a given seed, the output for a given
+/// sequence of [`Hasher`] writes is stable across versions of this crate, so
+/// serialized sketches remain compatible with newly inserted items. Changing
+/// the algorithm would be a breaking change and require a major version bump.
+///
+/// Note that the writes themselves come from the item's [`core::hash::Hash`]
+/// implementation, which this crate does not control. Use `insert_hash` with
+/// your own hash function if you need guarantees independent of `Hash`.
 pub type DefaultHasher = CloneBuildHasher<RandomDefaultHasher>;
 
 impl DefaultHasher {
@@ -160,4 +171,15 @@ mod test {
         let h2 = SipHasher13::new_with_key(&[0; 16]);
         assert_eq!(hash_all(h1), hash_all(h2),);
     }
+
+    /// If this fails, `DefaultHasher` output changed and previously serialized
+    /// sketches are no longer compatible.
+    #[test]
+    fn test_hasher_stable() {
+        let h = crate::DefaultHasher::seeded(&[0; 16]);
+        assert_eq!(crate::hash_one(&h, &42u64), 8880661182590738257);
+        assert_eq!(crate::hash_one(&h, "hyperloglockless"), 10363268780639834142);
+        let h = crate::DefaultHasher::seeded(&42u128.to_be_bytes());
+        assert_eq!(crate::hash_one(&h, &42u64), 12406579261874524161);
+    }
 }
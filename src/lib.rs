@@ -6,13 +6,13 @@ extern crate alloc;
 use alloc::{boxed::Box, vec::Vec};
 use core::hash::{BuildHasher, Hash};
 use core::iter::repeat;
-use core::sync::atomic::Ordering::Relaxed;
+use core::sync::atomic::Ordering::{self, Acquire, Relaxed, Release};
 
 #[cfg(feature = "loom")]
-pub(crate) use loom::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize};
+pub(crate) use loom::sync::atomic::{fence, AtomicBool, AtomicU64, AtomicU8, AtomicUsize};
 
 #[cfg(not(feature = "loom"))]
-pub(crate) use portable_atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize};
+pub(crate) use portable_atomic::{fence, AtomicBool, AtomicU64, AtomicU8, AtomicUsize};
 
 #[cfg(all(feature = "loom", feature = "serde"))]
 compile_error!("features `loom` and `serde` are mutually exclusive");
@@ -20,16 +20,25 @@ compile_error!("features `loom` and `serde` are mutually exclusive");
 mod atomic_f64;
 use atomic_f64::AtomicF64;
 mod beta;
+pub use beta::beta_correction;
 use beta::beta_horner;
+mod bias_tables;
 mod hasher;
 pub use hasher::DefaultHasher;
 mod error;
-pub use error::Error;
+pub use error::{CompatibilityError, Error};
 mod math;
 use math::*;
+mod mle;
+use mle::joint_mle_estimate;
+mod serde_version;
+use serde_version::SerdeVersion;
+pub use serde_version::SERDE_VERSION;
 
+mod compact;
+pub use compact::Compact4BitHyperLogLog;
 mod sparse;
-pub use sparse::HyperLogLogPlus;
+pub use sparse::{HyperLogLogPlus, SPARSE_MAX_PRECISION};
 mod buf;
 mod vint;
 
@@ -46,7 +55,8 @@ mod vint;
 ///
 /// let count = hll.count();
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Clone)]
+#[cfg_attr(feature = "verbose_debug", derive(Debug))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HyperLogLog<S = DefaultHasher> {
     /// `registers[k]` is the maximum trailing zeros for all 64-bit hashes
@@ -59,6 +69,10 @@ pub struct HyperLogLog<S = DefaultHasher> {
     sum: f64,
     correction: f64,
     updated_count: bool,
+    /// Zero-sized, only read when serialized.
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    serde_version: SerdeVersion,
 }
 
 /// HyperLogLog is a data structure for the "count-distinct problem",
@@ -75,9 +89,19 @@ pub struct HyperLogLog<S = DefaultHasher> {
 ///
 /// let count = hll.count();
 /// ```
-#[derive(Debug)]
+///
+/// # Versioning
+/// With `VERSIONED = true` ([`VersionedAtomicHyperLogLog`]), every register
+/// change also increments a version counter, which lets
+/// [`AtomicHyperLogLog::try_union`] detect concurrent changes to the sketch it
+/// reads. This costs an extra contended atomic increment per register change,
+/// so it is off by default. See [`AtomicHyperLogLog::into_versioned`].
+///
+/// The counter is an 8 byte field of every `AtomicHyperLogLog`, including
+/// unversioned ones, where it is never touched.
+#[cfg_attr(feature = "verbose_debug", derive(Debug))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct AtomicHyperLogLog<S = DefaultHasher> {
+pub struct AtomicHyperLogLog<S = DefaultHasher, const VERSIONED: bool = false> {
     /// `registers[k]` is the maximum trailing zeros for all 64-bit hashes
     /// assigned to kth register
     registers: Box<[AtomicU8]>,
@@ -88,8 +112,20 @@ pub struct AtomicHyperLogLog<S = DefaultHasher> {
     sum: AtomicF64,
     correction: f64,
     updated_count: AtomicBool,
+    /// Incremented before every register change if `VERSIONED`, see
+    /// [`AtomicHyperLogLog::try_union`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    version: AtomicU64,
+    /// Zero-sized, only read when serialized.
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    serde_version: SerdeVersion,
 }
 
+/// An [`AtomicHyperLogLog`] that tracks a version for
+/// [`AtomicHyperLogLog::try_union`], see [`AtomicHyperLogLog::into_versioned`].
+pub type VersionedAtomicHyperLogLog<S = DefaultHasher> = AtomicHyperLogLog<S, true>;
+
 impl<S: BuildHasher> HyperLogLog<S> {
     /// Returns a new `HyperLogLog` with `1 << precision` registers (1 byte
     /// each) using the provided hasher.
@@ -105,6 +141,7 @@ impl<S: BuildHasher> HyperLogLog<S> {
             registers: registers.into(),
             sum: f64::from(num_registers as u32),
             updated_count: true,
+            serde_version: SerdeVersion,
         }
     }
 }
@@ -124,12 +161,62 @@ impl<S: BuildHasher> AtomicHyperLogLog<S> {
             registers: data.into(),
             sum: AtomicF64::new(f64::from(num_registers as u32)),
             updated_count: true.into(),
+            serde_version: SerdeVersion,
+            version: AtomicU64::new(0),
+        }
+    }
+
+    /// Low level method to construct [`Self`] de/serializable parts.
+    pub fn from_parts(registers: Box<[AtomicU8]>, hasher: S, zeros: usize, sum: f64, updated_count: bool) -> Self {
+        let len = registers.len() as u64;
+        let precision = len.trailing_zeros();
+        assert_eq!(precision + len.leading_zeros(), 63, "resigers.len() not a power of 2");
+        assert_eq!(1 << precision, registers.len());
+        validate_precision(precision as u8);
+        Self {
+            hasher,
+            precision,
+            zeros: AtomicUsize::new(zeros),
+            correction: correction(registers.len()),
+            registers,
+            sum: AtomicF64::new(sum),
+            updated_count: AtomicBool::new(updated_count),
+            serde_version: SerdeVersion,
+            version: AtomicU64::new(0),
+        }
+    }
+
+    /// Converts `self` into a [`VersionedAtomicHyperLogLog`] with the same
+    /// registers, hasher, and count state, whose register changes can be
+    /// detected by [`AtomicHyperLogLog::try_union`].
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::AtomicHyperLogLog;
+    ///
+    /// let hll = AtomicHyperLogLog::seeded(12, 42);
+    /// hll.extend(0..1000);
+    /// let count = hll.count();
+    /// let hll = hll.into_versioned();
+    /// assert_eq!(hll.count(), count);
+    /// ```
+    pub fn into_versioned(self) -> VersionedAtomicHyperLogLog<S> {
+        AtomicHyperLogLog {
+            registers: self.registers,
+            precision: self.precision,
+            hasher: self.hasher,
+            zeros: self.zeros,
+            sum: self.sum,
+            correction: self.correction,
+            updated_count: self.updated_count,
+            version: AtomicU64::new(0),
+            serde_version: SerdeVersion,
         }
     }
 }
 
 macro_rules! impl_hll {
-    ($name:ident, $ismut:literal, $($m:ident)?) => {
+    ($name:ident, $ismut:literal, $($m:ident)?, [$($param:tt)*], [$($arg:tt)*]) => {
         impl $name {
             /// Returns a new [`Self`] with `1 << precision` registers (1 byte each)
             /// using the default hasher with a random seed.
@@ -142,236 +229,1955 @@ macro_rules! impl_hll {
             pub fn seeded(precision: u8, seed: u128) -> $name<DefaultHasher> {
                 $name::with_hasher(precision, DefaultHasher::seeded(&seed.to_be_bytes()))
             }
-        }
 
-        impl<S: BuildHasher> $name<S> {
-            /// Returns the number registers in `self`.
-            #[inline(always)]
-            pub fn len(&self) -> usize {
-                self.registers.len()
+            /// Returns a new [`Self`] using the default hasher with a random seed, with
+            /// the smallest precision whose error is at most `error`
+            /// ([`precision_for_error`]) and whose [`Self::theoretical_max_count`] is
+            /// more than twice `expected_cardinality`. The precision is raised one step
+            /// at a time until both hold.
+            ///
+            /// Returns `Err(Error::InvalidPrecision)` if `error` is not between 0 and 1,
+            /// or no precision in [`PRECISION_RANGE`] satisfies both. The theoretical
+            /// maximum is about `2^63` at every precision, so in practice only `error`
+            /// decides the precision.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let hll = ", stringify!($name), "::with_capacity_hint(1_000_000, 0.01).unwrap();")]
+            /// assert_eq!(hll.precision(), 14);
+            #[doc = concat!("assert!(", stringify!($name), "::with_capacity_hint(1000, 0.0001).is_err());")]
+            /// ```
+            pub fn with_capacity_hint(expected_cardinality: usize, error: f64) -> Result<$name<DefaultHasher>, Error> {
+                if !(0.0 < error && error < 1.0) {
+                    return Err(Error::InvalidPrecision);
+                }
+                let mut precision = precision_for_error(error).max(*PRECISION_RANGE.start());
+                while PRECISION_RANGE.contains(&precision) {
+                    let hll = $name::with_hasher(precision, DefaultHasher::default());
+                    if hll.theoretical_max_count() > 2.0 * expected_cardinality as f64 {
+                        return Ok(hll);
+                    }
+                    precision += 1;
+                }
+                Err(Error::InvalidPrecision)
             }
 
-            /// Returns the approximate number of elements in `self`.
-            #[inline]
-            pub fn count(&self) -> usize {
-                crate::math::round(self.raw_count()) as usize
+            /// Returns the estimate of a HyperLogLog with the given register values,
+            /// one byte per register, without constructing one. The precision is
+            /// inferred from the number of registers. This is the same value as
+            /// [`Self::estimate`] of a sketch with those registers, e.g. to count
+            /// serialized registers once.
+            ///
+            /// Returns `NaN` if the number of registers is not `1 << precision` for a
+            /// precision in [`PRECISION_RANGE`], or a register value is invalid
+            /// (greater than 65).
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::new(12);")]
+            /// hll.extend(0..1000);
+            /// let registers: Vec<u8> = hll.iter().collect();
+            #[doc = concat!("let count = ", stringify!($name), "::count_from_registers(&registers);")]
+            /// assert!((count - hll.estimate()).abs() < 1e-6);
+            #[doc = concat!("assert!(", stringify!($name), "::count_from_registers(&registers[1..]).is_nan());")]
+            /// ```
+            pub fn count_from_registers(registers: &[u8]) -> f64 {
+                let len = registers.len();
+                let precision = len.trailing_zeros();
+                let valid_len = len.is_power_of_two() && PRECISION_RANGE.contains(&(precision as u8));
+                if !valid_len || registers.iter().any(|r| *r as usize >= INV_POW2.len()) {
+                    return f64::NAN;
+                }
+                let (zeros, sum) = zeros_and_sum_of(registers.iter().copied());
+                let d = sum + beta_horner(zeros, precision);
+                correction(len) * (len * (len - zeros)) as f64 / d
             }
+        }
 
-            #[inline(always)]
-            fn raw_count_inner(&self, zeros: usize, sum: f64) -> f64 {
-                let d = sum + beta_horner(zeros, self.precision);
-                self.correction * (self.len() * (self.len() - zeros)) as f64 / d
+        impl<S, $($param)*> $name<S, $($arg)*> {
+            /// The smallest supported precision, the start of [`PRECISION_RANGE`].
+            pub const PRECISION_MIN: u8 = *PRECISION_RANGE.start();
+
+            /// The largest supported precision, the end of [`PRECISION_RANGE`].
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("type Hll = ", stringify!($name), ";")]
+            /// for precision in Hll::PRECISION_MIN..=Hll::PRECISION_MAX {
+            ///     assert_eq!(Hll::new(precision).precision(), precision);
+            /// }
+            /// ```
+            pub const PRECISION_MAX: u8 = *PRECISION_RANGE.end();
+        }
+
+        impl<S: BuildHasher, $($param)*> $name<S, $($arg)*> {
+            /// Returns the precision of `self`.
+            #[inline]
+            pub fn precision(&self) -> u8 {
+                self.precision as u8
             }
 
-            fn count_from_scratch(&self) -> f64 {
-                let mut data = [0usize; 66];
-                for r in self.iter() {
-                    data[r as usize] += 1;
-                }
-                let zeros = data[0] as usize;
-                let mut sum = zeros as f64;
-                for i in 1..=65 {
-                    sum += data[i] as f64 * INV_POW2[i];
-                }
-                self.raw_count_inner(zeros, sum)
+            /// Returns a reference to the hasher used by `self`.
+            ///
+            /// Sketches can only be merged meaningfully if they hash items the same
+            /// way, e.g. they were built with equal seeds.
+            #[inline]
+            pub fn hasher(&self) -> &S {
+                &self.hasher
             }
 
-            /// Inserts the item into the HyperLogLog.
+            /// Consumes `self`, returning the hasher it used.
             #[inline]
-            pub fn insert<T: Hash + ?Sized>(&$($m)? self, value: &T) {
-                self.insert_inner::<true>(hash_one(&self.hasher, value));
+            pub fn into_hasher(self) -> S {
+                self.hasher
             }
 
-            /// Inserts the hash of an item into the HyperLogLog.
+            /// Returns the number registers in `self`.
+            #[allow(clippy::len_without_is_empty)]
             #[inline(always)]
-            pub fn insert_hash(&$($m)? self, hash: u64) {
-                self.insert_inner::<true>(hash);
+            pub fn len(&self) -> usize {
+                self.registers.len()
             }
 
-            /// Inserts the item into the HyperLogLog, but skips maintaining the cached count.
-            ///
-            /// The registers are still updated exactly as in [`Self::insert`], but the
-            /// internal count state is invalidated. As a result, the next call to
-            /// [`Self::count`] or [`Self::raw_count`] will recompute the count by scanning
-            /// all registers.
+            /// Returns the number of bytes used by `self`, including the registers
+            /// on the heap.
+            #[inline]
+            pub fn memory_usage_bytes(&self) -> usize {
+                core::mem::size_of::<Self>() + self.len()
+            }
+
+            /// Folds every register value into an accumulator, in register order.
             ///
-            /// This is faster for insert-heavy workloads where counts are queried rarely.
-            /// The rough work for each operation:
-            /// - `insert`: 2
-            /// - `count`: 1
-            /// - `insert_lazy`: 1
-            /// - `count` (post `insert_lazy`): 2^precision (the number of registers)
+            /// Equivalent to `self.iter().fold(init, f)`.
             ///
             /// # Example
             /// ```
             #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
             ///
             #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::new(12);")]
+            /// hll.insert(&42);
             ///
-            /// hll.insert(&1);
-            /// let _ = hll.count(); // O(1)
-            /// hll.insert_lazy(&42);
-            /// let _ = hll.count(); // scans all registers
+            /// let non_zero = hll.fold_registers(0, |acc, r| acc + (r > 0) as usize);
+            /// assert_eq!(non_zero, 1);
             /// ```
             #[inline]
-            pub fn insert_lazy<T: Hash + ?Sized>(&$($m)? self, value: &T) {
-                self.insert_inner::<false>(hash_one(&self.hasher, value));
+            pub fn fold_registers<B, F: Fn(B, u8) -> B>(&self, init: B, f: F) -> B {
+                self.iter().fold(init, f)
             }
 
-            /// Inserts the hash of an item into the HyperLogLog without updating the count.
-            /// See [`Self::insert_lazy`].
-            #[inline(always)]
-            pub fn insert_hash_lazy(&$($m)? self, hash: u64) {
-                self.insert_inner::<false>(hash);
+            /// Returns the number of registers with each value: element `v` is the
+            /// number of registers equal to `v`. Register values are at most 65.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::new(12);")]
+            /// hll.insert_hash(0b100);
+            /// let histogram = hll.register_histogram();
+            /// assert_eq!(histogram[0], hll.len() - 1);
+            /// assert_eq!(histogram[3], 1);
+            /// ```
+            pub fn register_histogram(&self) -> [usize; 66] {
+                histogram_of(self.iter())
             }
 
-            /// Inserts all the items in `iter` into the `self`.
-            #[inline]
-            pub fn insert_all<'a, T: Hash + 'a, I: Iterator<Item = &'a T>>(&$($m)? self, iter: I) {
-                for val in iter {
-                    self.insert(val);
+            /// Returns the Shannon entropy, in nats, of the register values:
+            /// `-sum(p_v * ln(p_v))` where `p_v` is the fraction of registers equal to
+            /// `v` ([`Self::register_histogram`] divided by [`Self::len`]).
+            ///
+            /// This is a health metric for the sketch. Register values of a uniform
+            /// hash spread over a few adjacent values, so a sketch with many elements
+            /// has an entropy of roughly 2 nats regardless of its count. An entropy
+            /// near 0 means nearly all registers have the same value, e.g. a sketch
+            /// with few elements relative to [`Self::len`].
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::new(12);")]
+            /// assert_eq!(hll.register_entropy(), 0.0);
+            ///
+            /// hll.extend(0..1_000_000);
+            /// assert!(hll.register_entropy() > 1.0);
+            /// ```
+            pub fn register_entropy(&self) -> f64 {
+                let m = self.len() as f64;
+                self.register_histogram()
+                    .into_iter()
+                    .filter(|&count| count > 0)
+                    .map(|count| count as f64 / m * ln(m / count as f64))
+                    .sum()
+            }
+
+            /// Returns an iterator over the `k` registers with the highest values, as
+            /// `(index, value)` pairs sorted by descending value. Registers with equal
+            /// values are ordered by ascending index. Yields every register if `k` is
+            /// at least [`Self::len`].
+            ///
+            /// A few registers with values far above the rest can indicate a hash
+            /// function that isn't uniform. This keeps a min-heap of size `k` while
+            /// scanning the registers, taking O(m log k) time.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::new(4);")]
+            /// hll.insert_hash(0b100);
+            /// hll.insert_hash((3 << 60) | 0b10);
+            /// let top: Vec<_> = hll.iter_top_k_registers(3).collect();
+            /// assert_eq!(top, [(0, 3), (3, 2), (1, 0)]);
+            /// ```
+            pub fn iter_top_k_registers(&self, k: usize) -> impl Iterator<Item = (usize, u8)> {
+                use alloc::collections::BinaryHeap;
+                use core::cmp::Reverse;
+                let mut heap = BinaryHeap::with_capacity(k.min(self.len()) + 1);
+                for (index, value) in self.iter().enumerate() {
+                    heap.push(Reverse((value, Reverse(index))));
+                    if heap.len() > k {
+                        heap.pop();
+                    }
                 }
+                heap.into_sorted_vec()
+                    .into_iter()
+                    .map(|Reverse((value, Reverse(index)))| (index, value))
             }
 
-            /// Inserts all the items in `iter` into the `self`.
-            /// See [`Self::insert_lazy`].
-            #[inline]
-            pub fn insert_all_lazy<'a, T: Hash + 'a, I: Iterator<Item = &'a T>>(&$($m)? self, iter: I) {
-                for val in iter {
-                    self.insert_lazy(val);
+            /// Returns the expected fraction of registers with each value, given
+            /// [`Self::estimate`] elements were inserted with a uniform hash. Element
+            /// `v` is the fraction of registers expected to equal `v`, and the
+            /// fractions sum to 1.
+            ///
+            /// With `λ = estimate / m` elements per register, the number of elements
+            /// in a register is approximately Poisson distributed and each has a
+            /// `2^-k` chance of a value greater than `k`, so a register is at most `k`
+            /// with probability `exp(-λ * 2^-k)`.
+            ///
+            /// Comparing this to the normalized [`Self::register_histogram`] can
+            /// reveal a biased hash function.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::new(12);")]
+            /// hll.extend(0..10_000);
+            ///
+            /// let m = hll.len() as f64;
+            /// let expected = hll.expected_register_distribution();
+            /// for (v, &count) in hll.register_histogram().iter().enumerate() {
+            ///     assert!((count as f64 / m - expected[v]).abs() < 0.05);
+            /// }
+            /// ```
+            pub fn expected_register_distribution(&self) -> [f64; 66] {
+                let lambda = self.estimate() / self.len() as f64;
+                let mut res = [0.0; 66];
+                let mut prev = exp(-lambda);
+                res[0] = prev;
+                for (v, r) in res.iter_mut().enumerate().skip(1) {
+                    let at_most = exp(-lambda * INV_POW2[v]);
+                    *r = at_most - prev;
+                    prev = at_most;
                 }
+                res[65] += 1.0 - prev;
+                res
             }
 
-            /// Counts the current items in `self` plus the items in `iter` and returns the count.
-            /// This is optimized based on the size hint of `iter`: if `iter` is very long, each insert
-            /// does not update the count in O(1). Instead it will cheaply insert items and then scan
-            /// and all the registers at once at then end.
+            /// Returns an iterator over `(index, value)` of each register with a
+            /// non-zero value.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::new(12);")]
+            /// hll.insert_hash(0b100);
+            /// assert_eq!(hll.iter_nonzero().collect::<Vec<_>>(), vec![(0, 3)]);
+            /// ```
             #[inline]
-            pub fn count_once<'a, T: Hash + 'a, I: Iterator<Item = &'a T>>(&$($m)? self, iter: I) -> f64 {
-                let len = iter.size_hint().0;
-                let thresh = self.len();
-                match len {
-                    l if l > thresh => {
-                        self.insert_all_lazy(iter);
-                    }
-                    _ => {
-                        self.insert_all(iter);
-                    }
-                }
-                self.raw_count()
+            pub fn iter_nonzero(&self) -> impl Iterator<Item = (usize, u8)> + '_ {
+                self.iter().enumerate().filter(|&(_, v)| v > 0)
             }
 
-            /// Merges another HyperLogLog into `self`, updating the count.
-            /// Returns `Err(Error::IncompatibleLength)` if the two HyperLogLogs have
-            /// different length ([`Self::len`]).
+            /// Returns an iterator over `(index, value)` of each register of `self`
+            /// whose value differs from the same register of `baseline`, e.g. to send
+            /// only the changes of a sketch since `baseline` was sent. Returns
+            /// `Err(Error::IncompatibleLength)` if the two HyperLogLogs have different
+            /// length ([`Self::len`]).
             ///
-            /// This does not verify that the HLLs use the same hasher or seed.
-            /// If they are different then `self` will be "corrupted".
-            pub fn union(&$($m)? self, other: &Self) -> Result<(), Error> {
-                if self.len() != other.len() {
+            /// The changes can be applied to a copy of `baseline` with
+            /// [`Self::union_from_bytes`] or by setting each register.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let baseline = ", stringify!($name), "::seeded(12, 42);")]
+            #[doc = concat!("let ", $ismut, "hll = baseline.clone();")]
+            /// hll.insert_hash(0b100);
+            /// let changed: Vec<_> = hll.iter_changed(&baseline).unwrap().collect();
+            /// assert_eq!(changed, vec![(0, 3)]);
+            /// ```
+            pub fn iter_changed<'a>(
+                &'a self,
+                baseline: &'a Self,
+            ) -> Result<impl Iterator<Item = (usize, u8)> + 'a, Error> {
+                if self.len() != baseline.len() {
                     return Err(Error::IncompatibleLength);
                 }
+                Ok(core::iter::zip(self.iter(), baseline.iter())
+                    .enumerate()
+                    .filter(|(_, (new, old))| new != old)
+                    .map(|(i, (new, _))| (i, new)))
+            }
 
-                // TODO? if self.hasher != other.hasher { ... }
-
-                if self.updated_count() {
-                    other.iter().enumerate().for_each(|(i, x)| self.update::<true>(x, i));
-                } else {
-                    other.iter().enumerate().for_each(|(i, x)| self.update::<false>(x, i));
+            /// Merges `(index, value)` register updates, e.g. from [`Self::iter_changed`],
+            /// into `self`, updating the count. Like [`Self::union`], each register
+            /// becomes the maximum of its value and the update.
+            ///
+            /// Returns `Err(Error::IndexOutOfBounds)` if an index is not less than
+            /// [`Self::len`], or `Err(Error::InvalidEncoding)` if a value is out of
+            /// range. The updates before the invalid one are still applied.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::seeded(12, 42);")]
+            /// let baseline = hll.clone();
+            #[doc = concat!("let ", $ismut, "replica = baseline.clone();")]
+            /// hll.extend(0..1000);
+            /// replica.apply_delta(hll.iter_changed(&baseline).unwrap()).unwrap();
+            /// assert_eq!(replica, hll);
+            /// ```
+            pub fn apply_delta<I: IntoIterator<Item = (usize, u8)>>(&$($m)? self, delta: I) -> Result<(), Error> {
+                for (index, value) in delta {
+                    if index >= self.len() {
+                        return Err(Error::IndexOutOfBounds);
+                    }
+                    if value as usize >= INV_POW2.len() {
+                        return Err(Error::InvalidEncoding);
+                    }
+                    self.update::<true>(value, index);
                 }
-
                 Ok(())
             }
 
-        }
+            /// Returns the largest value across all registers, i.e. one more than the
+            /// longest run of trailing zeros observed in any inserted hash. Returns 0
+            /// if nothing has been inserted.
+            #[inline]
+            pub fn max_register_value(&self) -> u8 {
+                self.iter().max().unwrap_or(0)
+            }
 
-        impl<S: BuildHasher> PartialEq for $name<S> {
-            fn eq(&self, other: &Self) -> bool {
-                if self.len() != other.len() {
-                    return false;
-                }
-                core::iter::zip(self.iter(), other.iter()).all(|(l, r)| l == r)
+            /// Returns the approximate number of elements in `self`.
+            #[inline]
+            pub fn count(&self) -> usize {
+                crate::math::round(self.estimate()) as usize
             }
-        }
-        impl<S: BuildHasher> Eq for $name<S> {}
-    };
-}
 
-impl_hll!(HyperLogLog, "mut ", mut);
-impl_hll!(AtomicHyperLogLog, "",);
+            /// Returns the approximate number of elements in `self` as a float.
+            #[deprecated(note = "renamed to `estimate`")]
+            #[inline]
+            pub fn raw_count(&self) -> f64 {
+                self.estimate()
+            }
 
-impl<S: BuildHasher> HyperLogLog<S> {
-    #[inline(always)]
-    fn insert_inner<const UPDATE_COUNT: bool>(&mut self, hash: u64) {
-        let index = (hash >> (64 - self.precision)) as usize;
-        let new = 1 + hash.trailing_zeros() as u8;
-        self.update::<UPDATE_COUNT>(new, index);
-    }
+            /// Returns the version of the serialized form `self` is written with when
+            /// using the `serde` feature, [`SERDE_VERSION`](crate::SERDE_VERSION).
+            /// The version is part of the serialized form but not stored in memory.
+            #[inline]
+            pub fn serde_version(&self) -> u8 {
+                SERDE_VERSION
+            }
 
-    #[inline(always)]
-    fn update<const UPDATE_COUNT: bool>(&mut self, new: u8, index: usize) {
-        let old = self.registers[index];
-        self.registers[index] = new.max(old);
-        if UPDATE_COUNT && self.updated_count {
-            self.zeros -= (old == 0 && new > 0) as usize;
-            let diff = INV_POW2[old as usize] - INV_POW2[new as usize];
-            self.sum -= diff.max(0.0);
-        } else {
-            self.updated_count = false;
-        }
-    }
+            /// Returns roughly how many more distinct elements need to be inserted
+            /// before the approximate count reaches `target`, or `0.0` if it already has.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::new(12);")]
+            /// assert_eq!(hll.estimate_inserts_remaining(100.0), 100.0);
+            ///
+            /// hll.extend(0..1000);
+            /// assert_eq!(hll.estimate_inserts_remaining(100.0), 0.0);
+            /// ```
+            #[inline]
+            pub fn estimate_inserts_remaining(&self, target: f64) -> f64 {
+                (target - self.estimate()).max(0.0)
+            }
 
-    /// Returns an iterator over the value of each register.
+            /// Returns [`Self::estimate`] clamped to `0.0..=f64::MAX`, with `NaN`
+            /// mapped to `0.0`.
+            ///
+            /// [`Self::estimate`] is always finite and non-negative for a consistent
+            /// HyperLogLog, but may not be if the cached count state is inconsistent
+            /// with the registers, e.g. after [`Self::from_parts`] with mismatched
+            /// parts. This guarantees a value that is safe to convert or compare.
+            #[inline]
+            pub fn count_safe(&self) -> f64 {
+                let count = self.estimate();
+                let res = match count.is_nan() {
+                    true => 0.0,
+                    false => f64::max(0.0, count.min(f64::MAX)),
+                };
+                debug_assert!(res.is_finite());
+                res
+            }
+
+            /// Returns [`Self::estimate`] as an `f32`, for callers that store or
+            /// compute with single precision floats.
+            ///
+            /// The estimate itself is still computed with `f64`. `f32` represents
+            /// integers exactly up to `2^24`, beyond which the result is rounded to
+            /// a relative precision of about `6e-8`, far below the estimate's error.
+            ///
+            /// The cached sum of `2^-register` stays `f64`. Rounding an `f32` sum on
+            /// every update drifts it upward: at precision 18, the estimate from such
+            /// a sum is about 10% too high after `2^28` inserts, 50 times the
+            /// standard error.
+            #[inline]
+            pub fn count_f32(&self) -> f32 {
+                self.estimate() as f32
+            }
+
+            /// Returns the LogLog estimate of the number of elements in `self`, which
+            /// uses the geometric mean of `2^register` instead of the harmonic mean:
+            /// `α * m * 2^(sum(register) / m)` with `α ≈ 0.39701`.
+            ///
+            /// This is experimental and less accurate than [`Self::estimate`]: its
+            /// relative error is about `1.30 / sqrt(m)` instead of `1.04 / sqrt(m)`,
+            /// it is strongly biased while many registers are zero, and outlier
+            /// registers skew it. It is useful for comparing estimators.
+            pub fn count_geometric_mean(&self) -> f64 {
+                const ALPHA: f64 = 0.39701;
+                let m = self.len() as f64;
+                let sum: u64 = self.iter().map(u64::from).sum();
+                ALPHA * m * pow(2.0, sum as f64 / m)
+            }
+
+            /// Returns the signed relative bias, `(raw - true) / true`, of the raw
+            /// HyperLogLog estimate `raw = α * m^2 / sum(2^-register)` at the current
+            /// registers, according to the empirical bias tables of HyperLogLog++.
+            /// The true count is taken to be `raw - bias`, the bias corrected estimate
+            /// of HyperLogLog++. Returns `0.0` if `self` is empty, and for raw
+            /// estimates above `5 * m`, where the tables have no bias.
+            ///
+            /// The raw estimate is heavily biased for small cardinalities, which
+            /// [`Self::estimate`] removes using the LogLog-Beta correction instead of
+            /// the tables. This is a diagnostic of how biased the raw estimate is, not
+            /// a correction itself.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::seeded(12, 42);")]
+            /// hll.extend(0..1000);
+            /// // The raw estimate is several times the true count.
+            /// assert!(hll.estimate_bias() > 1.0);
+            /// hll.extend(0..100_000);
+            /// assert_eq!(hll.estimate_bias(), 0.0);
+            /// ```
+            pub fn estimate_bias(&self) -> f64 {
+                let (zeros, sum) = self.zeros_and_sum();
+                let m = self.len() as f64;
+                let raw = self.correction * m * m / sum;
+                if zeros == self.len() || raw > 5.0 * m {
+                    return 0.0;
+                }
+                let bias = bias_tables::estimate_bias(raw, self.precision);
+                bias / (raw - bias)
+            }
+
+            /// Returns the estimate of `self` if every register held `64 - precision`,
+            /// the largest value decided by the hash bits outside the register index
+            /// alone: `63 - precision` trailing zeros followed by a one. Larger values
+            /// need all `64 - precision` of those bits to be zero, which happens to
+            /// each hash with probability `2^-(64 - precision)`. Estimates of larger
+            /// cardinalities are unreliable, so this bounds the cardinalities `self`
+            /// can tell apart.
+            ///
+            /// This is far above any realistic cardinality at every supported
+            /// precision, e.g. about `2^63` at precision 14; in practice, the
+            /// estimate's error matters long before this limit.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let hll = ", stringify!($name), "::new(14);")]
+            /// assert!(hll.theoretical_max_count() > (1u64 << 62) as f64);
+            /// ```
+            pub fn theoretical_max_count(&self) -> f64 {
+                theoretical_max_count_of(self.precision)
+            }
+
+            /// Returns the precision `self` should be rebuilt with, given its current
+            /// estimate.
+            ///
+            /// The relative error of the estimate depends only on the precision, not
+            /// on the count (see [`error_for_precision`]), so a sketch keeps the same
+            /// error as it grows and this is [`Self::precision`] unless the sketch is
+            /// saturated. `self` is saturated if its [`Self::theoretical_max_count`] is
+            /// not more than twice [`Self::estimate`]. In that case, this is the
+            /// smallest larger precision whose theoretical maximum is more than twice
+            /// the estimate, or [`Self::PRECISION_MAX`] if none is. The theoretical
+            /// maximum is about `2^63` at every precision, so saturation needs hashes
+            /// with almost all bits zero and is not reached with a uniform hash.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::new(12);")]
+            /// hll.extend(0..1_000_000);
+            /// assert_eq!(hll.precision_recommendation(), 12);
+            /// ```
+            pub fn precision_recommendation(&self) -> u8 {
+                let target = 2.0 * self.estimate();
+                if self.theoretical_max_count() > target {
+                    return self.precision();
+                }
+                (self.precision() + 1..=Self::PRECISION_MAX)
+                    .find(|&precision| theoretical_max_count_of(precision as u32) > target)
+                    .unwrap_or(Self::PRECISION_MAX)
+            }
+
+            /// Returns the components [`Self::estimate`] is computed from, for use in
+            /// external estimators: the number of zero registers `z`, the sum of
+            /// `2^-register` over all registers, and the bias correction factor `α`
+            /// for the number of registers `m`.
+            ///
+            /// The estimate is `α * m * (m - z) / (sum + β(z))`, where `β` is
+            /// [`beta_correction`]. The zeros and sum are recomputed from the
+            /// registers if the count state is stale, e.g. after
+            /// [`Self::insert_lazy`].
+            ///
+            /// # Example
+            /// ```
+            /// use hyperloglockless::beta_correction;
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::new(12);")]
+            /// hll.extend(0..1000);
+            ///
+            /// let (zeros, sum, alpha) = hll.raw_components();
+            /// let m = hll.len() as f64;
+            /// let d = sum + beta_correction(zeros, 12);
+            /// let estimate = alpha * m * (m - zeros as f64) / d;
+            /// assert!((estimate - hll.estimate()).abs() < 1e-6);
+            /// ```
+            pub fn raw_components(&self) -> (usize, f64, f64) {
+                let (zeros, sum) = self.zeros_and_sum();
+                (zeros, sum, self.correction)
+            }
+
+            /// Panics with a message naming the violated invariant if the internal
+            /// state of `self` is inconsistent:
+            /// - the number of registers is `1 << precision`,
+            /// - the cached correction factor matches the number of registers,
+            /// - if the count state is up to date, the cached number of zero registers
+            ///   and sum of `2^-register` match the registers (the sum up to floating
+            ///   point rounding).
+            ///
+            /// This scans every register, and is meant for tests and fuzzing, e.g.
+            /// after each operation that changes `self`. It should not be called
+            /// concurrently with inserts.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::new(12);")]
+            /// hll.extend(0..1000);
+            /// hll.assert_invariants();
+            /// ```
+            pub fn assert_invariants(&self) {
+                assert_eq!(self.len(), 1 << self.precision, "number of registers is not 1 << precision");
+                assert_eq!(self.correction, correction(self.len()), "correction does not match the number of registers");
+                if !self.updated_count() {
+                    return;
+                }
+                let (zeros, sum) = self.zeros_and_sum();
+                let (expected_zeros, expected_sum) = self.zeros_and_sum_from_scratch();
+                assert_eq!(zeros, expected_zeros, "zeros does not match the number of zero registers");
+                assert!(
+                    (sum - expected_sum).abs() <= 1e-9 * expected_sum,
+                    "sum {} does not match the sum of 2^-register {}",
+                    sum,
+                    expected_sum
+                );
+            }
+
+            /// Returns the fraction of registers of `self` that are non-zero, between
+            /// `0.0` and `1.0`.
+            #[inline]
+            pub fn fill_factor(&self) -> f64 {
+                let (zeros, _) = self.zeros_and_sum();
+                (self.len() - zeros) as f64 / self.len() as f64
+            }
+
+            /// Returns `true` if `hash` may have been inserted into `self`, i.e. the
+            /// register [`Self::insert_hash`] would update already holds at least the
+            /// value `hash` would set. Returns `false` if `hash` was definitely not
+            /// inserted. This is an O(1) lookup.
+            ///
+            /// Any other hash with the same register and at most as many trailing
+            /// zeros also sets the register, so this is a weak filter: for a random
+            /// hash the false positive rate is `1 - sum / m` over the `m` registers,
+            /// where `sum` is the sum of `2^-register`. This is at most
+            /// [`Self::fill_factor`], and approaches 1 as the registers grow.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::seeded(12, 42);")]
+            /// hll.insert_hash(1 << 63);
+            /// assert!(hll.contains_hash(1 << 63));
+            /// assert!(!hll.contains_hash(1));
+            /// ```
+            #[inline]
+            pub fn contains_hash(&self, hash: u64) -> bool {
+                let index = (hash >> (64 - self.precision)) as usize;
+                let value = 1 + hash.trailing_zeros() as u8;
+                self.register(index) >= value
+            }
+
+            /// Returns [`Self::estimate`] and an empirical `confidence` interval around
+            /// it, as `(estimate, lower, upper)`.
+            ///
+            /// The interval is a percentile bootstrap: the registers are resampled
+            /// with replacement 200 times, each resample is estimated like `self`,
+            /// and `lower` and `upper` are the quantiles of those estimates that
+            /// leave `(1 - confidence) / 2` of them on either side. Unlike
+            /// `estimate * error_for_precision(precision)`, the interval reflects
+            /// the actual registers and may be asymmetric. The resamples are
+            /// deterministic, so the same registers always give the same interval.
+            ///
+            /// This is a diagnostic tool: it scans the registers 200 times and
+            /// allocates a copy of them.
+            ///
+            /// # Panics
+            /// Panics if `confidence` is not between 0 and 1.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::seeded(12, 42);")]
+            /// hll.extend(0..100_000);
+            /// let (estimate, lower, upper) = hll.count_with_bootstrap_ci(0.95);
+            /// assert!(lower < estimate && estimate < upper);
+            /// assert!(lower < 100_000.0 && 100_000.0 < upper);
+            /// ```
+            pub fn count_with_bootstrap_ci(&self, confidence: f64) -> (f64, f64, f64) {
+                assert!(0.0 < confidence && confidence < 1.0);
+                const RESAMPLES: usize = 200;
+                let registers: Vec<u8> = self.iter().collect();
+                let m = registers.len() as u64;
+                let mut estimates: Vec<f64> = (0..RESAMPLES as u64)
+                    .map(|i| {
+                        let resample = (0..m).map(|j| registers[(splitmix64(i * m + j) % m) as usize]);
+                        let (zeros, sum) = zeros_and_sum_of(resample);
+                        self.raw_count_inner(zeros, sum)
+                    })
+                    .collect();
+                estimates.sort_unstable_by(f64::total_cmp);
+                let tail = ((1.0 - confidence) / 2.0 * (RESAMPLES - 1) as f64) as usize;
+                (self.estimate(), estimates[tail], estimates[RESAMPLES - 1 - tail])
+            }
+
+            /// Returns the linear counting estimate of the number of elements in
+            /// `self`, `m * ln(m / zeros)` for `m` registers of which `zeros` are
+            /// zero. Returns `f64::INFINITY` if no register is zero.
+            ///
+            /// Classic HyperLogLog switches to linear counting for small counts.
+            /// [`Self::estimate`] does not: the LogLog-Beta correction applied to the
+            /// harmonic sum accounts for zero registers at every count. This
+            /// uncorrected estimate is useful as a diagnostic, e.g. to see where the
+            /// two diverge. It is accurate while the count is small relative to `m`
+            /// and degrades as registers fill.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::new(14);")]
+            /// assert_eq!(hll.linear_count(), 0.0);
+            ///
+            /// hll.extend(0..1000);
+            /// assert!((hll.linear_count() - 1000.0).abs() < 50.0);
+            /// ```
+            pub fn linear_count(&self) -> f64 {
+                let (zeros, _) = self.zeros_and_sum();
+                let m = self.len() as f64;
+                m * ln(m / zeros as f64)
+            }
+
+            #[inline(always)]
+            fn raw_count_inner(&self, zeros: usize, sum: f64) -> f64 {
+                let d = sum + beta_horner(zeros, self.precision);
+                self.correction * (self.len() * (self.len() - zeros)) as f64 / d
+            }
+
+            /// Returns the number of zero registers and the sum of `2^-register` over
+            /// all registers, by scanning every register.
+            fn zeros_and_sum_from_scratch(&self) -> (usize, f64) {
+                zeros_and_sum_of(self.iter())
+            }
+
+            fn count_from_scratch(&self) -> f64 {
+                let (zeros, sum) = self.zeros_and_sum_from_scratch();
+                self.raw_count_inner(zeros, sum)
+            }
+
+            /// Inserts the item into the HyperLogLog.
+            #[inline]
+            pub fn insert<T: Hash + ?Sized>(&$($m)? self, value: &T) {
+                self.insert_inner::<true>(hash_one(&self.hasher, value));
+            }
+
+            /// Inserts the hash of an item into the HyperLogLog.
+            #[inline(always)]
+            pub fn insert_hash(&$($m)? self, hash: u64) {
+                self.insert_inner::<true>(hash);
+            }
+
+            /// Inserts `value` into the HyperLogLog, hashing it with the splitmix64
+            /// finalizer instead of the hasher of `self`, which is considerably faster.
+            ///
+            /// The hash does not depend on the hasher or seed of `self`, so
+            /// `insert_u64(x)` and `insert(&x)` insert different hashes: use one or
+            /// the other consistently, including across sketches that are merged.
+            /// Unlike a seeded hasher, this offers no protection from adversarial
+            /// inputs. `insert_u64(x)` and `insert_u32(x as u32)` insert the same hash.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::new(12);")]
+            /// for x in 0..1000 {
+            ///     hll.insert_u64(x);
+            /// }
+            /// assert!(hll.count().abs_diff(1000) < 100);
+            /// ```
+            #[inline(always)]
+            pub fn insert_u64(&$($m)? self, value: u64) {
+                self.insert_inner::<true>(splitmix64(value));
+            }
+
+            /// Inserts `value` into the HyperLogLog. This is the same as
+            /// `self.insert_u64(value as u64)`; see [`Self::insert_u64`].
+            #[inline(always)]
+            pub fn insert_u32(&$($m)? self, value: u32) {
+                self.insert_u64(value as u64);
+            }
+
+            /// Inserts an item from two independent hashes: `h1` picks the register,
+            /// from its top [`Self::precision`] bits, and `h2` gives the register
+            /// value, from its trailing zeros.
+            ///
+            /// With a single hash, the index bits and the trailing zeros share 64
+            /// bits. Separate hashes keep the full 64 bits for the register value at
+            /// any precision. `insert_double_hash(h, h)` is the same as `insert_hash(h)`.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "a = ", stringify!($name), "::seeded(12, 42);")]
+            #[doc = concat!("let ", $ismut, "b = ", stringify!($name), "::seeded(12, 42);")]
+            /// a.insert_double_hash(7 << 60, 1 << 20);
+            /// b.insert_hash((7 << 60) | (1 << 20));
+            /// assert_eq!(a, b);
+            /// ```
+            #[inline(always)]
+            pub fn insert_double_hash(&$($m)? self, h1: u64, h2: u64) {
+                let index = (h1 >> (64 - self.precision)) as usize;
+                let new = 1 + h2.trailing_zeros() as u8;
+                self.update::<true>(new, index);
+            }
+
+            /// Inserts a 128-bit hash of an item, using the high 64 bits for the
+            /// register and the low 64 bits for its value.
+            /// See [`Self::insert_double_hash`].
+            #[inline(always)]
+            pub fn insert_128(&$($m)? self, hash: u128) {
+                self.insert_double_hash((hash >> 64) as u64, hash as u64);
+            }
+
+            /// Inserts all the `(h1, h2)` hash pairs in `hashes` into `self`.
+            /// See [`Self::insert_double_hash`].
+            #[inline]
+            pub fn insert_all_hashed<I: IntoIterator<Item = (u64, u64)>>(&$($m)? self, hashes: I) {
+                for (h1, h2) in hashes {
+                    self.insert_double_hash(h1, h2);
+                }
+            }
+
+            /// Inserts the item into the HyperLogLog and returns `true` if a register
+            /// was increased, i.e. the insert changed `self`.
+            ///
+            /// This is a probabilistic novelty signal, not a membership test: `false`
+            /// means `value` may or may not be new, as most new items do not increase
+            /// a register once `self` holds many items. `true` means `value` was
+            /// (almost certainly) not inserted before.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::seeded(12, 42);")]
+            /// assert!(hll.insert_and_report("42"));
+            /// assert!(!hll.insert_and_report("42"));
+            /// ```
+            #[inline]
+            pub fn insert_and_report<T: Hash + ?Sized>(&$($m)? self, value: &T) -> bool {
+                self.insert_inner::<true>(hash_one(&self.hasher, value))
+            }
+
+            /// Inserts `weight` distinct synthetic items derived from `value`, so that
+            /// `value` contributes roughly `weight` to the count. This is intended for
+            /// sampled streams where each sampled item represents `weight` items.
+            ///
+            /// The synthetic items are the hashes of `(hash(value), i)` for `i` in
+            /// `0..weight`, so inserting the same `value` and `weight` again does not
+            /// change the count, and a larger weight for the same `value` only adds
+            /// the difference. The count is as accurate as if `weight` distinct items
+            /// were inserted, but the cost is `weight` inserts.
+            ///
+            /// Experimental: the semantics may change.
+            #[doc(hidden)]
+            #[inline]
+            pub fn weighted_insert<T: Hash + ?Sized>(&$($m)? self, value: &T, weight: u64) {
+                let hash = hash_one(&self.hasher, value);
+                for i in 0..weight {
+                    self.insert_inner::<true>(hash_one(&self.hasher, &(hash, i)));
+                }
+            }
+
+            /// Inserts the item into the HyperLogLog, but skips maintaining the cached count.
+            ///
+            /// The registers are still updated exactly as in [`Self::insert`], but the
+            /// internal count state is invalidated. As a result, the next call to
+            /// [`Self::count`] or [`Self::estimate`] will recompute the count by scanning
+            /// all registers.
+            ///
+            /// This is faster for insert-heavy workloads where counts are queried rarely.
+            /// The rough work for each operation:
+            /// - `insert`: 2
+            /// - `count`: 1
+            /// - `insert_lazy`: 1
+            /// - `count` (post `insert_lazy`): 2^precision (the number of registers)
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::new(12);")]
+            ///
+            /// hll.insert(&1);
+            /// let _ = hll.count(); // O(1)
+            /// hll.insert_lazy(&42);
+            /// let _ = hll.count(); // scans all registers
+            /// ```
+            #[inline]
+            pub fn insert_lazy<T: Hash + ?Sized>(&$($m)? self, value: &T) {
+                self.insert_inner::<false>(hash_one(&self.hasher, value));
+            }
+
+            /// Inserts the hash of an item into the HyperLogLog without updating the count.
+            /// See [`Self::insert_lazy`].
+            #[inline(always)]
+            pub fn insert_hash_lazy(&$($m)? self, hash: u64) {
+                self.insert_inner::<false>(hash);
+            }
+
+            /// Inserts all the items in `iter` into the `self`.
+            #[inline]
+            pub fn insert_all<'a, T: Hash + 'a, I: Iterator<Item = &'a T>>(&$($m)? self, iter: I) {
+                for val in iter {
+                    self.insert(val);
+                }
+            }
+
+            /// Inserts all the items in `iter` into the `self`.
+            /// See [`Self::insert_lazy`].
+            #[inline]
+            pub fn insert_all_lazy<'a, T: Hash + 'a, I: Iterator<Item = &'a T>>(&$($m)? self, iter: I) {
+                for val in iter {
+                    self.insert_lazy(val);
+                }
+            }
+
+            /// Inserts all the hashes in `hashes` into `self`.
+            /// See [`Self::insert_hash`].
+            #[inline]
+            pub fn insert_many_hashes<I: IntoIterator<Item = u64>>(&$($m)? self, hashes: I) {
+                for hash in hashes {
+                    self.insert_hash(hash);
+                }
+            }
+
+            /// Counts the current items in `self` plus the items in `iter` and returns the count.
+            /// This is optimized based on the size hint of `iter`: if `iter` is very long, each insert
+            /// does not update the count in O(1). Instead it will cheaply insert items and then scan
+            /// and all the registers at once at then end.
+            #[inline]
+            pub fn count_once<'a, T: Hash + 'a, I: Iterator<Item = &'a T>>(&$($m)? self, iter: I) -> f64 {
+                let len = iter.size_hint().0;
+                let thresh = self.len();
+                match len {
+                    l if l > thresh => {
+                        self.insert_all_lazy(iter);
+                    }
+                    _ => {
+                        self.insert_all(iter);
+                    }
+                }
+                self.estimate()
+            }
+
+            /// Returns `Ok(())` if `other` can be merged into `self` with [`Self::union`],
+            /// otherwise a [`CompatibilityError`] describing both sketches.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::{", stringify!($name), ", CompatibilityError};")]
+            ///
+            #[doc = concat!("let hll = ", stringify!($name), "::seeded(12, 42);")]
+            #[doc = concat!("let other = ", stringify!($name), "::seeded(14, 42);")]
+            /// assert!(hll.compatibility_check(&hll.clone()).is_ok());
+            /// assert_eq!(
+            ///     hll.compatibility_check(&other),
+            ///     Err(CompatibilityError {
+            ///         self_precision: 12,
+            ///         other_precision: 14,
+            ///         self_len: 1 << 12,
+            ///         other_len: 1 << 14,
+            ///     })
+            /// );
+            /// ```
+            pub fn compatibility_check(&self, other: &Self) -> Result<(), CompatibilityError> {
+                if self.len() == other.len() {
+                    return Ok(());
+                }
+                Err(CompatibilityError {
+                    self_precision: self.precision(),
+                    other_precision: other.precision(),
+                    self_len: self.len(),
+                    other_len: other.len(),
+                })
+            }
+
+            /// Merges another HyperLogLog into `self`, updating the count.
+            /// Returns `Err(Error::IncompatibleLength)` if the two HyperLogLogs have
+            /// different length ([`Self::len`]).
+            ///
+            /// This does not verify that the HLLs use the same hasher or seed.
+            /// If they are different then `self` will be "corrupted".
+            pub fn union(&$($m)? self, other: &Self) -> Result<(), Error> {
+                if self.len() != other.len() {
+                    return Err(Error::IncompatibleLength);
+                }
+
+                // TODO? if self.hasher != other.hasher { ... }
+
+                if self.updated_count() {
+                    other.iter().enumerate().for_each(|(i, x)| {
+                        self.update::<true>(x, i);
+                    });
+                } else {
+                    other.iter().enumerate().for_each(|(i, x)| {
+                        self.update::<false>(x, i);
+                    });
+                }
+
+                Ok(())
+            }
+
+            /// Merges raw register values, one byte per register, into `self`,
+            /// updating the count. This is equivalent to [`Self::union`] with a
+            /// HyperLogLog made of those registers, without allocating it.
+            ///
+            /// Returns `Err(Error::InvalidRegisterCount)` if `bytes.len()` differs
+            /// from [`Self::len`], or `Err(Error::InvalidEncoding)` if a register
+            /// value is out of range. `self` is not modified on error.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "hll = ", stringify!($name), "::seeded(12, 42);")]
+            #[doc = concat!("let ", $ismut, "other = ", stringify!($name), "::seeded(12, 42);")]
+            /// other.insert(&1);
+            /// let bytes: Vec<u8> = other.iter().collect();
+            /// hll.union_from_bytes(&bytes).unwrap();
+            /// assert_eq!(hll, other);
+            /// ```
+            pub fn union_from_bytes(&$($m)? self, bytes: &[u8]) -> Result<(), Error> {
+                if self.len() != bytes.len() {
+                    return Err(Error::InvalidRegisterCount {
+                        expected: self.len(),
+                        got: bytes.len(),
+                    });
+                }
+                if bytes.iter().any(|&x| x as usize >= INV_POW2.len()) {
+                    return Err(Error::InvalidEncoding);
+                }
+                if self.updated_count() {
+                    bytes.iter().enumerate().for_each(|(i, &x)| {
+                        self.update::<true>(x, i);
+                    });
+                } else {
+                    bytes.iter().enumerate().for_each(|(i, &x)| {
+                        self.update::<false>(x, i);
+                    });
+                }
+                Ok(())
+            }
+
+            /// Returns the sum over all registers of the bit Hamming distance between
+            /// the register values of `self` and `other`, a rough measure of how
+            /// different two sketches are. Returns `Err(Error::IncompatibleLength)` if
+            /// the two HyperLogLogs have different length ([`Self::len`]).
+            ///
+            /// This is not a standard HyperLogLog operation and does not estimate any
+            /// set quantity.
+            pub fn xor_distance(&self, other: &Self) -> Result<u64, Error> {
+                if self.len() != other.len() {
+                    return Err(Error::IncompatibleLength);
+                }
+                Ok(core::iter::zip(self.iter(), other.iter())
+                    .map(|(l, r)| (l ^ r).count_ones() as u64)
+                    .sum())
+            }
+
+            /// Returns the Euclidean (L2) distance between the register values of
+            /// `self` and `other`, `sqrt(sum((r_i - s_i)^2))`. Returns
+            /// `Err(Error::IncompatibleLength)` if the two HyperLogLogs have
+            /// different length ([`Self::len`]).
+            ///
+            /// Like [`Self::xor_distance`], this measures how different the sketches
+            /// are, not how different the underlying sets are. Sketches built from
+            /// the same items with the same hasher have a distance of `0.0`.
+            pub fn sketch_distance(&self, other: &Self) -> Result<f64, Error> {
+                if self.len() != other.len() {
+                    return Err(Error::IncompatibleLength);
+                }
+                let sum: u64 = core::iter::zip(self.iter(), other.iter())
+                    .map(|(l, r)| (l.abs_diff(r) as u64).pow(2))
+                    .sum();
+                Ok(sqrt(sum as f64))
+            }
+
+            /// Returns the approximate number of elements in the intersection of the
+            /// sets represented by `a`, `b`, and `c`, using inclusion-exclusion over
+            /// the counts of each sketch, each pairwise union, and the three-way
+            /// union. Returns `Err(Error::IncompatibleLength)` if the HyperLogLogs do
+            /// not all have the same length ([`Self::len`]).
+            ///
+            /// The unions are estimated directly from the register maxima, so no
+            /// sketch is modified or cloned. Since the errors of the seven counts add
+            /// up, the estimate is only useful when the intersection is a sizable
+            /// fraction of the three-way union. Negative estimates are clamped to
+            /// `0.0`.
+            ///
+            /// # Example
+            /// ```rust
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "a = ", stringify!($name), "::seeded(14, 42);")]
+            #[doc = concat!("let ", $ismut, "b = ", stringify!($name), "::seeded(14, 42);")]
+            #[doc = concat!("let ", $ismut, "c = ", stringify!($name), "::seeded(14, 42);")]
+            /// a.extend(0..6000);
+            /// b.extend(3000..9000);
+            /// c.extend(4000..10000);
+            ///
+            #[doc = concat!("let both = ", stringify!($name), "::count_intersection_three(&a, &b, &c).unwrap();")]
+            /// assert!((1500.0..2500.0).contains(&both));
+            /// ```
+            pub fn count_intersection_three(a: &Self, b: &Self, c: &Self) -> Result<f64, Error> {
+                if a.len() != b.len() || a.len() != c.len() {
+                    return Err(Error::IncompatibleLength);
+                }
+                let union_count = |registers: &mut dyn Iterator<Item = u8>| {
+                    let (zeros, sum) = zeros_and_sum_of(registers);
+                    a.raw_count_inner(zeros, sum)
+                };
+                let ab = union_count(&mut core::iter::zip(a.iter(), b.iter()).map(|(x, y)| x.max(y)));
+                let ac = union_count(&mut core::iter::zip(a.iter(), c.iter()).map(|(x, y)| x.max(y)));
+                let bc = union_count(&mut core::iter::zip(b.iter(), c.iter()).map(|(x, y)| x.max(y)));
+                let abc = union_count(
+                    &mut core::iter::zip(core::iter::zip(a.iter(), b.iter()), c.iter())
+                        .map(|((x, y), z)| x.max(y).max(z)),
+                );
+                let both = a.estimate() + b.estimate() + c.estimate() - ab - ac - bc + abc;
+                Ok(both.max(0.0))
+            }
+
+            /// Returns the approximate number of elements in the intersection of the
+            /// sets represented by `self` and `other`, using the joint maximum
+            /// likelihood estimator of Ertl (2017). Returns
+            /// `Err(Error::IncompatibleLength)` if the HyperLogLogs have different
+            /// length ([`Self::len`]).
+            ///
+            /// Inclusion-exclusion, `|A| + |B| - |A ∪ B|`, only uses the three counts,
+            /// so its error is that of the union and grows with it regardless of how
+            /// small the intersection is. This estimator instead models the register
+            /// pairs directly: how often each register of `self` is below, above, or
+            /// equal to that of `other`, and at which values, which is much more
+            /// accurate for intersections that are small relative to the union.
+            ///
+            /// The estimate is found numerically, taking a few thousand evaluations
+            /// of the likelihood over the register histograms after one scan of the
+            /// registers, so this is slower than [`Self::estimate`].
+            ///
+            /// # Example
+            /// ```rust
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "a = ", stringify!($name), "::seeded(14, 42);")]
+            #[doc = concat!("let ", $ismut, "b = ", stringify!($name), "::seeded(14, 42);")]
+            /// a.extend(0..100_000);
+            /// b.extend(95_000..195_000);
+            ///
+            /// let both = a.count_intersection_mle(&b).unwrap();
+            /// assert!((4000.0..6000.0).contains(&both));
+            /// ```
+            pub fn count_intersection_mle(&self, other: &Self) -> Result<f64, Error> {
+                if self.len() != other.len() {
+                    return Err(Error::IncompatibleLength);
+                }
+                let [_, _, both] = joint_mle_estimate(core::iter::zip(self.iter(), other.iter()), self.precision);
+                Ok(both)
+            }
+        }
+
+        // Constructors without a `Self` argument are only implemented for the
+        // default type parameters, so that callers don't need type annotations.
+        #[cfg(feature = "serde")]
+        impl<S: BuildHasher> $name<S> {
+            /// Deserializes [`Self`] from any supported version of its serialized
+            /// form, migrating it to the current version. Serialized sketches from
+            /// before the version was added are version 1.
+            ///
+            /// There is currently only one version, so no migration is needed.
+            /// Deserializing with [`serde::Deserialize`] directly instead fails for
+            /// versions that need migration.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "before = ", stringify!($name), "::seeded(12, 42);")]
+            /// before.extend(0..1000);
+            /// let json = serde_json::to_string(&before).unwrap();
+            /// let mut deserializer = serde_json::Deserializer::from_str(&json);
+            #[doc = concat!("let after = ", stringify!($name), "::from_any_version(&mut deserializer).unwrap();")]
+            /// assert_eq!(before, after);
+            /// ```
+            pub fn from_any_version<'de, D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+                S: serde::Deserialize<'de>,
+            {
+                <Self as serde::Deserialize>::deserialize(deserializer)
+            }
+        }
+
+        #[cfg(feature = "cbor")]
+        impl<S, $($param)*> $name<S, $($arg)*> {
+            /// Returns the serde form of `self`, including the hasher, encoded as CBOR.
+            /// [`HyperLogLog`] and [`AtomicHyperLogLog`] have the same serde form, so
+            /// either can be read back with [`Self::from_cbor_bytes`].
+            ///
+            /// Returns `Err(Error::InvalidEncoding)` if the hasher fails to serialize.
+            ///
+            /// # Example
+            /// ```
+            #[doc = concat!("use hyperloglockless::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let ", $ismut, "before = ", stringify!($name), "::seeded(12, 42);")]
+            /// before.extend(0..1000);
+            /// let bytes = before.to_cbor_bytes().unwrap();
+            #[doc = concat!("let after = ", stringify!($name), "::from_cbor_bytes(&bytes).unwrap();")]
+            /// assert_eq!(before, after);
+            /// ```
+            pub fn to_cbor_bytes(&self) -> Result<Vec<u8>, Error>
+            where
+                S: serde::Serialize,
+            {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(self, &mut bytes).map_err(|_| Error::InvalidEncoding)?;
+                Ok(bytes)
+            }
+        }
+
+        #[cfg(feature = "cbor")]
+        impl<S> $name<S> {
+            /// Reconstructs a sketch, including its hasher, from CBOR returned by
+            /// [`HyperLogLog::to_cbor_bytes`] or [`AtomicHyperLogLog::to_cbor_bytes`].
+            ///
+            /// Returns `Err(Error::InvalidEncoding)` if `bytes` is not valid CBOR of
+            /// the serde form of a sketch.
+            pub fn from_cbor_bytes(bytes: &[u8]) -> Result<Self, Error>
+            where
+                S: serde::de::DeserializeOwned,
+            {
+                ciborium::from_reader(bytes).map_err(|_| Error::InvalidEncoding)
+            }
+        }
+
+        impl<S: BuildHasher, $($param)*> PartialEq for $name<S, $($arg)*> {
+            fn eq(&self, other: &Self) -> bool {
+                if self.len() != other.len() {
+                    return false;
+                }
+                core::iter::zip(self.iter(), other.iter()).all(|(l, r)| l == r)
+            }
+        }
+        impl<S: BuildHasher, $($param)*> Eq for $name<S, $($arg)*> {}
+
+        /// Shows a summary of `self` instead of every register value. Enable the
+        /// `verbose_debug` feature for the full register dump.
+        #[cfg(not(feature = "verbose_debug"))]
+        impl<S: BuildHasher + core::fmt::Debug, $($param)*> core::fmt::Debug for $name<S, $($arg)*> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.debug_struct(stringify!($name))
+                    .field("precision", &self.precision)
+                    .field("count", &format_args!("~{}", self.count()))
+                    .field("fill_factor", &self.fill_factor())
+                    .field("hasher", &self.hasher)
+                    .finish()
+            }
+        }
+    };
+}
+
+impl_hll!(HyperLogLog, "mut ", mut, [], []);
+impl_hll!(AtomicHyperLogLog, "", , [const VERSIONED: bool], [VERSIONED]);
+
+impl<S: BuildHasher> HyperLogLog<S> {
+    #[inline(always)]
+    fn insert_inner<const UPDATE_COUNT: bool>(&mut self, hash: u64) -> bool {
+        let index = (hash >> (64 - self.precision)) as usize;
+        let new = 1 + hash.trailing_zeros() as u8;
+        self.update::<UPDATE_COUNT>(new, index)
+    }
+
+    /// Sets register `index` to `new` if larger, returning whether it changed.
+    #[inline(always)]
+    fn update<const UPDATE_COUNT: bool>(&mut self, new: u8, index: usize) -> bool {
+        let old = self.registers[index];
+        self.registers[index] = new.max(old);
+        if UPDATE_COUNT && self.updated_count {
+            self.zeros -= (old == 0 && new > 0) as usize;
+            let diff = INV_POW2[old as usize] - INV_POW2[new as usize];
+            self.sum -= diff.max(0.0);
+        } else {
+            self.updated_count = false;
+        }
+        new > old
+    }
+
+    /// Returns an iterator over the value of each register.
     #[inline]
     pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
-        self.registers.iter().map(|x| *x)
+        self.registers.iter().copied()
+    }
+
+    #[inline(always)]
+    fn register(&self, index: usize) -> u8 {
+        self.registers[index]
+    }
+
+    /// Returns an iterator over consecutive, non-overlapping chunks of `N`
+    /// register values, e.g. to process registers `N` at a time with SIMD or to
+    /// checksum them. Returns `None` if `N` is zero or does not divide
+    /// [`Self::len`].
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLog;
+    ///
+    /// let mut hll = HyperLogLog::seeded(12, 42);
+    /// hll.extend(0..1000);
+    /// let zeros: usize = hll
+    ///     .iter_register_chunks::<16>()
+    ///     .unwrap()
+    ///     .map(|chunk| chunk.iter().filter(|r| **r == 0).count())
+    ///     .sum();
+    /// assert_eq!(zeros, hll.iter().filter(|r| *r == 0).count());
+    /// assert!(hll.iter_register_chunks::<3>().is_none());
+    /// ```
+    pub fn iter_register_chunks<const N: usize>(&self) -> Option<impl Iterator<Item = &[u8; N]> + '_> {
+        if N == 0 || self.len() % N != 0 {
+            return None;
+        }
+        Some(
+            self.registers
+                .chunks_exact(N)
+                .map(|chunk| <&[u8; N]>::try_from(chunk).expect("chunks have length N")),
+        )
+    }
+
+    #[inline]
+    fn updated_count(&self) -> bool {
+        self.updated_count
+    }
+
+    /// Returns the number of zero registers and the sum of `2^-register`,
+    /// recomputing them if the cached values are stale.
+    #[inline]
+    fn zeros_and_sum(&self) -> (usize, f64) {
+        match self.updated_count {
+            true => (self.zeros, self.sum),
+            false => self.zeros_and_sum_from_scratch(),
+        }
+    }
+
+    /// Returns the approximate number of elements in `self` as a float.
+    /// [`Self::count`] is this estimate rounded to an integer.
+    #[inline]
+    pub fn estimate(&self) -> f64 {
+        match self.updated_count {
+            true => self.raw_count_inner(self.zeros, self.sum),
+            false => self.count_from_scratch(),
+        }
+    }
+
+    /// Low level method to expose de/serializable parts of `self`.
+    pub fn parts(&self) -> (&[u8], &S, usize, f64, bool) {
+        (&self.registers, &self.hasher, self.zeros, self.sum, self.updated_count)
+    }
+
+    /// Low level method to construct [`Self`] de/serializable parts.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLog;
+    ///
+    /// let mut before = HyperLogLog::seeded(16, 42);
+    /// before.extend(1000..=2000);
+    /// let (x, y, z, w, u) = before.parts();
+    /// let after = HyperLogLog::from_parts(x.into(), y.clone(), z, w, u);
+    /// assert_eq!(before, after);
+    /// ```
+    pub fn from_parts(registers: Box<[u8]>, hasher: S, zeros: usize, sum: f64, updated_count: bool) -> Self {
+        let len = registers.len() as u64;
+        let precision = len.trailing_zeros();
+        assert_eq!(precision + len.leading_zeros(), 63, "resigers.len() not a power of 2");
+        assert_eq!(1 << precision, registers.len());
+        validate_precision(precision as u8);
+        Self {
+            hasher,
+            precision,
+            zeros,
+            correction: correction(registers.len()),
+            registers,
+            sum,
+            updated_count,
+            serde_version: SerdeVersion,
+        }
+    }
+
+    /// Constructs [`Self`] directly from its parts without any validation or
+    /// recomputation, for deserializing trusted data in hot loops. Prefer
+    /// [`Self::from_bytes`] or [`Self::from_parts`] otherwise.
+    ///
+    /// # Safety
+    /// The caller must ensure that:
+    /// - `precision` is in [`PRECISION_RANGE`],
+    /// - `registers.len() == 1 << precision`,
+    /// - every register value is at most `65`,
+    /// - `zeros` is the number of zero registers, and `sum` is the sum of
+    ///   `2^-register` over all registers.
+    ///
+    /// Methods of the returned [`HyperLogLog`] rely on these invariants.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLog;
+    ///
+    /// let mut before = HyperLogLog::seeded(12, 42);
+    /// before.extend(0..1000);
+    /// let (registers, hasher, zeros, sum, _) = before.parts();
+    /// // SAFETY: the parts come from a valid HyperLogLog with an updated count.
+    /// let after = unsafe { HyperLogLog::from_raw_parts(12, registers.into(), zeros, sum, hasher.clone()) };
+    /// assert_eq!(before, after);
+    /// assert_eq!(before.estimate(), after.estimate());
+    /// ```
+    pub unsafe fn from_raw_parts(precision: u8, registers: Box<[u8]>, zeros: usize, sum: f64, hasher: S) -> Self {
+        Self {
+            hasher,
+            precision: precision as u32,
+            zeros,
+            correction: correction(registers.len()),
+            registers,
+            sum,
+            updated_count: true,
+            serde_version: SerdeVersion,
+        }
+    }
+}
+
+impl<S: BuildHasher> HyperLogLog<S> {
+    /// Merges `other` into `self` like [`Self::union`], taking `other` by value
+    /// for callers that no longer need it. Returns
+    /// `Err(Error::IncompatibleLength)` if the two HyperLogLogs have different
+    /// length ([`Self::len`]).
+    ///
+    /// Instead of updating the count state register by register, the registers
+    /// are merged with a branch-free element-wise max that the compiler
+    /// vectorizes, and the count state is then recomputed in one pass. This is
+    /// somewhat faster than [`Self::union`] when many registers change.
+    ///
+    /// This does not verify that the HLLs use the same hasher or seed.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLog;
+    ///
+    /// let mut hll = HyperLogLog::seeded(18, 42);
+    /// let mut other = HyperLogLog::seeded(18, 42);
+    /// hll.extend(0..1000);
+    /// other.extend(500..2000);
+    /// hll.merge_into(other).unwrap();
+    ///
+    /// let mut control = HyperLogLog::seeded(18, 42);
+    /// control.extend(0..2000);
+    /// assert_eq!(hll, control);
+    /// ```
+    pub fn merge_into(&mut self, other: Self) -> Result<(), Error> {
+        if self.len() != other.len() {
+            return Err(Error::IncompatibleLength);
+        }
+        for (r, o) in self.registers.iter_mut().zip(other.registers.iter()) {
+            *r = (*r).max(*o);
+        }
+        if self.updated_count {
+            self.recompute_sum_and_zeros();
+        }
+        Ok(())
+    }
+
+    /// Sets each register of `self` to the absolute difference between it and
+    /// the corresponding register of `other`, `max(a, b) - min(a, b)`.
+    /// Returns `Err(Error::IncompatibleLength)` if the two HyperLogLogs have
+    /// different length ([`Self::len`]).
+    ///
+    /// This is not a set operation: the symmetric difference of two sets can't
+    /// be represented by a HyperLogLog. The resulting registers are not those
+    /// of any set of elements, so [`Self::estimate`] and every other estimator
+    /// of this crate are meaningless on them, as are further inserts and
+    /// unions. This is only meant for research on register-level
+    /// approximations of set operations.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLog;
+    ///
+    /// let mut hll = HyperLogLog::seeded(4, 42);
+    /// let mut other = HyperLogLog::seeded(4, 42);
+    /// hll.insert_hash(0b1000);
+    /// other.insert_hash(0b10);
+    /// hll.union_symmetric_difference(&other).unwrap();
+    /// assert_eq!(hll.iter().next(), Some(2));
+    /// ```
+    pub fn union_symmetric_difference(&mut self, other: &Self) -> Result<(), Error> {
+        if self.len() != other.len() {
+            return Err(Error::IncompatibleLength);
+        }
+        for (r, o) in self.registers.iter_mut().zip(other.registers.iter()) {
+            *r = r.abs_diff(*o);
+        }
+        if self.updated_count {
+            self.recompute_sum_and_zeros();
+        }
+        Ok(())
+    }
+
+    /// Splits the registers into `num_buckets` equal, consecutive groups and
+    /// returns the approximate number of elements in each group, as if each
+    /// group were its own HyperLogLog.
+    ///
+    /// Bucket `i` holds the elements whose hashes start with `i` in their top
+    /// `log2(num_buckets)` bits. This is the same as calling
+    /// [`Self::subsketch`] for every bucket and taking its estimate, but in one
+    /// pass without copying the registers.
+    ///
+    /// Returns `Err(Error::IncompatibleLength)` if `num_buckets` does not
+    /// divide [`Self::len`] or the groups would have fewer than 16 registers.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLog;
+    ///
+    /// let mut hll = HyperLogLog::new(14);
+    /// hll.extend(0..100_000);
+    ///
+    /// let counts = hll.count_per_bucket(4).unwrap();
+    /// assert_eq!(counts.len(), 4);
+    /// let total: f64 = counts.iter().sum();
+    /// assert!((total - hll.estimate()).abs() < 0.05 * hll.estimate());
+    /// ```
+    pub fn count_per_bucket(&self, num_buckets: usize) -> Result<Vec<f64>, Error> {
+        if num_buckets == 0 || self.len() % num_buckets != 0 || self.len() / num_buckets < 16 {
+            return Err(Error::IncompatibleLength);
+        }
+        Ok(self
+            .registers
+            .chunks(self.len() / num_buckets)
+            .map(HyperLogLog::count_from_registers)
+            .collect())
+    }
+
+    /// Lowers the precision of `self` to `target` in place, keeping the maximum
+    /// register value of each group of `1 << (self.precision() - target)`
+    /// registers. This yields the same registers as if every item had been
+    /// inserted into a HyperLogLog of precision `target` with the same hasher.
+    ///
+    /// The register memory is reallocated to the smaller size and the count is
+    /// recomputed. Returns `Err(Error::InvalidPrecision)` if `target` is greater
+    /// than [`Self::precision`] or not supported.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLog;
+    ///
+    /// let mut hll = HyperLogLog::seeded(18, 42);
+    /// hll.extend(0..10_000);
+    /// hll.downgrade_precision(12).unwrap();
+    ///
+    /// let mut control = HyperLogLog::seeded(12, 42);
+    /// control.extend(0..10_000);
+    /// assert_eq!(hll, control);
+    /// ```
+    pub fn downgrade_precision(&mut self, target: u8) -> Result<(), Error> {
+        if target > self.precision() || !PRECISION_RANGE.contains(&target) {
+            return Err(Error::InvalidPrecision);
+        }
+        let group = 1 << (self.precision() - target);
+        let mut registers = core::mem::take(&mut self.registers).into_vec();
+        for i in 0..(registers.len() / group) {
+            registers[i] = registers[i * group..(i + 1) * group].iter().copied().max().unwrap();
+        }
+        registers.truncate(1 << target);
+        self.registers = registers.into_boxed_slice();
+        self.precision = target as u32;
+        self.correction = correction(self.len());
+        self.recompute_sum_and_zeros();
+        Ok(())
+    }
+
+    /// Recomputes the cached count state of `self` by scanning all registers,
+    /// making the next call to [`Self::count`] O(1) again.
+    ///
+    /// This is useful after [`Self::insert_lazy`], or to repair the count
+    /// state if it is inconsistent with the registers, e.g. after
+    /// [`Self::from_parts`] with mismatched parts.
+    pub fn recompute_sum_and_zeros(&mut self) {
+        (self.zeros, self.sum) = self.zeros_and_sum_from_scratch();
+        self.updated_count = true;
     }
 
-    #[inline]
-    fn updated_count(&self) -> bool {
-        self.updated_count
+    /// Returns the registers of `self` encoded as bytes: the magic byte
+    /// [`BYTES_MAGIC`], the format version [`BYTES_VERSION`], the precision,
+    /// then one byte per register. The hasher is not included.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLog;
+    ///
+    /// let mut before = HyperLogLog::seeded(12, 42);
+    /// before.extend(0..1000);
+    /// let bytes = before.to_bytes();
+    /// assert_eq!(bytes[..3], [b'H', 1, 12]);
+    /// let after = HyperLogLog::from_bytes(&bytes, before.parts().1.clone()).unwrap();
+    /// assert_eq!(before, after);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(3 + self.len());
+        bytes.extend_from_slice(&[BYTES_MAGIC, BYTES_VERSION, self.precision()]);
+        bytes.extend_from_slice(&self.registers);
+        bytes
     }
 
-    /// Returns the approximate number of elements in `self`.
-    #[inline]
-    pub fn raw_count(&self) -> f64 {
-        match self.updated_count {
-            true => self.raw_count_inner(self.zeros, self.sum),
-            false => self.count_from_scratch(),
+    /// Reconstructs a [`HyperLogLog`] from bytes returned by [`Self::to_bytes`],
+    /// using `hasher`, which should be the same hasher as the original.
+    ///
+    /// Returns `Err(Error::UnsupportedVersion)` if the format version is not
+    /// [`BYTES_VERSION`], `Err(Error::InvalidPrecision)` if the precision is
+    /// not supported, `Err(Error::InvalidRegisterCount)` if the number of
+    /// registers doesn't match the precision, or `Err(Error::InvalidEncoding)`
+    /// if the header is missing or a register value is out of range.
+    pub fn from_bytes(bytes: &[u8], hasher: S) -> Result<Self, Error> {
+        let [magic, version, precision, registers @ ..] = bytes else {
+            return Err(Error::InvalidEncoding);
+        };
+        if *magic != BYTES_MAGIC {
+            return Err(Error::InvalidEncoding);
         }
+        if *version != BYTES_VERSION {
+            return Err(Error::UnsupportedVersion {
+                version: u32::from(*version),
+            });
+        }
+        Self::from_precision_and_registers(*precision, registers, hasher)
     }
 
-    /// Low level method to expose de/serializable parts of `self`.
-    pub fn parts<'a>(&'a self) -> (&'a [u8], &'a S, usize, f64, bool) {
-        (&self.registers, &self.hasher, self.zeros, self.sum, self.updated_count)
+    /// Reconstructs a [`HyperLogLog`] from its precision and one byte per
+    /// register, validating both.
+    fn from_precision_and_registers(precision: u8, registers: &[u8], hasher: S) -> Result<Self, Error> {
+        if !PRECISION_RANGE.contains(&precision) {
+            return Err(Error::InvalidPrecision);
+        }
+        if registers.len() != 1 << precision {
+            return Err(Error::InvalidRegisterCount {
+                expected: 1 << precision,
+                got: registers.len(),
+            });
+        }
+        if registers.iter().any(|&x| x as usize >= INV_POW2.len()) {
+            return Err(Error::InvalidEncoding);
+        }
+        let mut hll = Self::from_parts(registers.into(), hasher, 0, 0.0, false);
+        hll.recompute_sum_and_zeros();
+        Ok(hll)
     }
 
-    /// Low level method to construct [`Self`] de/serializable parts.
+    /// Returns [`Self::to_bytes`] encoded as standard base64.
     ///
     /// # Example
     /// ```
     /// use hyperloglockless::HyperLogLog;
     ///
-    /// let mut before = HyperLogLog::seeded(16, 42);
-    /// before.extend(1000..=2000);
-    /// let (x, y, z, w, u) = before.parts();
-    /// let after = HyperLogLog::from_parts(x.into(), y.clone(), z, w, u);
+    /// let mut before = HyperLogLog::seeded(12, 42);
+    /// before.extend(0..1000);
+    /// let encoded = before.to_base64();
+    /// let after = HyperLogLog::from_base64(&encoded, before.parts().1.clone()).unwrap();
     /// assert_eq!(before, after);
     /// ```
-    pub fn from_parts(registers: Box<[u8]>, hasher: S, zeros: usize, sum: f64, updated_count: bool) -> Self {
-        let len = registers.len() as u64;
+    #[cfg(feature = "base64")]
+    pub fn to_base64(&self) -> alloc::string::String {
+        use base64::Engine;
+        base64::engine::general_purpose::STANDARD.encode(self.to_bytes())
+    }
+
+    /// Reconstructs a [`HyperLogLog`] from a string returned by
+    /// [`Self::to_base64`], using `hasher`.
+    ///
+    /// Returns `Err(Error::Base64DecodeError)` if `s` is not valid base64,
+    /// otherwise the same errors as [`Self::from_bytes`].
+    #[cfg(feature = "base64")]
+    pub fn from_base64(s: &str, hasher: S) -> Result<Self, Error> {
+        use base64::Engine;
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(s)
+            .map_err(Error::Base64DecodeError)?;
+        Self::from_bytes(&bytes, hasher)
+    }
+
+    /// Returns [`Self::to_bytes`] encoded as lowercase hex, two characters per
+    /// byte, e.g. for debugging or shell pipelines.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLog;
+    ///
+    /// let mut before = HyperLogLog::seeded(4, 42);
+    /// before.insert_hash(1);
+    /// let hex = before.to_hex();
+    /// assert_eq!(hex, "48010401000000000000000000000000000000");
+    /// let after = HyperLogLog::from_hex(&hex, before.parts().1.clone()).unwrap();
+    /// assert_eq!(before, after);
+    /// ```
+    pub fn to_hex(&self) -> alloc::string::String {
+        const DIGITS: &[u8; 16] = b"0123456789abcdef";
+        self.to_bytes()
+            .into_iter()
+            .flat_map(|b| [DIGITS[(b >> 4) as usize] as char, DIGITS[(b & 0xF) as usize] as char])
+            .collect()
+    }
+
+    /// Reconstructs a [`HyperLogLog`] from a string returned by
+    /// [`Self::to_hex`], using `hasher`. Upper and lowercase digits are
+    /// accepted.
+    ///
+    /// Returns `Err(Error::HexDecodeError)` if `s` is not valid hex, otherwise
+    /// the same errors as [`Self::from_bytes`].
+    pub fn from_hex(s: &str, hasher: S) -> Result<Self, Error> {
+        let s = s.as_bytes();
+        if s.len() % 2 != 0 {
+            return Err(Error::HexDecodeError { index: s.len() });
+        }
+        let digit = |index: usize| match s[index] {
+            c @ b'0'..=b'9' => Ok(c - b'0'),
+            c @ b'a'..=b'f' => Ok(c - b'a' + 10),
+            c @ b'A'..=b'F' => Ok(c - b'A' + 10),
+            _ => Err(Error::HexDecodeError { index }),
+        };
+        let bytes = (0..s.len())
+            .step_by(2)
+            .map(|i| Ok((digit(i)? << 4) | digit(i + 1)?))
+            .collect::<Result<Vec<u8>, Error>>()?;
+        Self::from_bytes(&bytes, hasher)
+    }
+
+    /// Returns `self` as JSON: an object with the format `version` (currently
+    /// `1`), the `precision`, and the `registers` as a standard base64 string,
+    /// one byte per register. The hasher is not included.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLog;
+    ///
+    /// let mut before = HyperLogLog::seeded(4, 42);
+    /// before.insert_hash(1);
+    /// let json = before.to_json();
+    /// assert_eq!(json, r#"{"version":1,"precision":4,"registers":"AQAAAAAAAAAAAAAAAAAAAA=="}"#);
+    /// let after = HyperLogLog::from_json(&json, before.parts().1.clone()).unwrap();
+    /// assert_eq!(before, after);
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> alloc::string::String {
+        use base64::Engine;
+        let envelope = JsonEnvelope {
+            version: JSON_VERSION,
+            precision: self.precision(),
+            registers: base64::engine::general_purpose::STANDARD.encode(&self.registers),
+        };
+        serde_json::to_string(&envelope).expect("serializing to a string does not fail")
+    }
+
+    /// Reconstructs a [`HyperLogLog`] from JSON returned by [`Self::to_json`],
+    /// using `hasher`, which should be the same hasher as the original.
+    ///
+    /// Returns `Err(Error::InvalidEncoding)` if `s` is not valid JSON,
+    /// `Err(Error::UnsupportedVersion)` if its format `version` is not
+    /// supported, `Err(Error::Base64DecodeError)` if the registers are
+    /// not valid base64, otherwise the same errors as [`Self::from_bytes`].
+    #[cfg(feature = "json")]
+    pub fn from_json(s: &str, hasher: S) -> Result<Self, Error> {
+        use base64::Engine;
+        let envelope: JsonEnvelope = serde_json::from_str(s).map_err(|_| Error::InvalidEncoding)?;
+        if envelope.version != JSON_VERSION {
+            return Err(Error::UnsupportedVersion {
+                version: envelope.version,
+            });
+        }
+        let registers = base64::engine::general_purpose::STANDARD
+            .decode(envelope.registers)
+            .map_err(Error::Base64DecodeError)?;
+        Self::from_precision_and_registers(envelope.precision, &registers, hasher)
+    }
+}
+
+/// The first byte of [`HyperLogLog::to_bytes`], `b'H'`.
+pub const BYTES_MAGIC: u8 = 0x48;
+
+/// The version of the byte format written by [`HyperLogLog::to_bytes`], its
+/// second byte.
+pub const BYTES_VERSION: u8 = 1;
+
+/// The version of the JSON format written by [`HyperLogLog::to_json`].
+#[cfg(feature = "json")]
+const JSON_VERSION: u32 = 1;
+
+#[cfg(feature = "json")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct JsonEnvelope {
+    version: u32,
+    precision: u8,
+    registers: alloc::string::String,
+}
+
+impl<S: BuildHasher + Clone> HyperLogLog<S> {
+    /// Returns a new [`HyperLogLog`] made of the registers
+    /// `start_register..end_register` of `self`, which approximates the number
+    /// of elements whose hashes fall in those registers.
+    ///
+    /// Returns `Err(Error::IndexOutOfBounds)` if the range is out of bounds, or
+    /// `Err(Error::IncompatibleLength)` if its length is not a power of two of
+    /// a supported precision.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLog;
+    ///
+    /// let mut hll = HyperLogLog::new(16);
+    /// hll.extend(0..100_000);
+    ///
+    /// // Roughly a quarter of the elements.
+    /// let quarter = hll.subsketch(0, 1 << 14).unwrap();
+    /// assert_eq!(quarter.precision(), 14);
+    /// ```
+    pub fn subsketch(&self, start_register: usize, end_register: usize) -> Result<HyperLogLog<S>, Error> {
+        if start_register > end_register || end_register > self.len() {
+            return Err(Error::IndexOutOfBounds);
+        }
+        let len = end_register - start_register;
         let precision = len.trailing_zeros();
-        assert_eq!(precision + len.leading_zeros(), 63, "resigers.len() not a power of 2");
-        assert_eq!(1 << precision, registers.len());
-        validate_precision(precision as u8);
-        Self {
-            hasher,
+        if !len.is_power_of_two() || !PRECISION_RANGE.contains(&(precision as u8)) {
+            return Err(Error::IncompatibleLength);
+        }
+        let mut sub = Self {
+            hasher: self.hasher.clone(),
             precision,
-            zeros,
-            correction: correction(registers.len()),
-            registers,
-            sum,
-            updated_count,
+            zeros: 0,
+            correction: correction(len),
+            registers: self.registers[start_register..end_register].into(),
+            sum: 0.0,
+            updated_count: true,
+            serde_version: SerdeVersion,
+        };
+        sub.recompute_sum_and_zeros();
+        Ok(sub)
+    }
+
+    /// Returns the union of all of `sketches`: a copy of the first, with the
+    /// registers of the others merged in by an element-wise max, and the count
+    /// state recomputed once at the end. This is faster than calling
+    /// [`Self::union`] for each sketch, which updates the count state on every
+    /// register change.
+    ///
+    /// Returns `Err(Error::Empty)` if `sketches` is empty, or
+    /// `Err(Error::IncompatibleLength)` if the sketches have different length
+    /// ([`Self::len`]). This does not verify that the HLLs use the same hasher
+    /// or seed.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLog;
+    ///
+    /// let sketches: Vec<_> = (0..10)
+    ///     .map(|i| {
+    ///         let mut hll = HyperLogLog::seeded(12, 42);
+    ///         hll.extend(i * 1000..(i + 1) * 1000);
+    ///         hll
+    ///     })
+    ///     .collect();
+    /// let merged = HyperLogLog::reduce(&sketches).unwrap();
+    ///
+    /// let mut control = HyperLogLog::seeded(12, 42);
+    /// control.extend(0..10_000);
+    /// assert_eq!(merged, control);
+    /// ```
+    pub fn reduce(sketches: &[Self]) -> Result<Self, Error> {
+        let (first, rest) = sketches.split_first().ok_or(Error::Empty)?;
+        if rest.iter().any(|hll| hll.len() != first.len()) {
+            return Err(Error::IncompatibleLength);
+        }
+        let mut merged = first.clone();
+        for other in rest {
+            for (r, o) in merged.registers.iter_mut().zip(other.registers.iter()) {
+                *r = (*r).max(*o);
+            }
+        }
+        merged.recompute_sum_and_zeros();
+        Ok(merged)
+    }
+
+    /// Returns a copy of `self` with precision `new_precision`.
+    ///
+    /// Lowering the precision by `k` folds each run of `2^k` registers into one
+    /// by taking their max, exactly like [`Self::downgrade_precision`]: the
+    /// result is the same as if the elements had been inserted at
+    /// `new_precision`.
+    ///
+    /// Raising the precision by `k` splits each register into `2^k` registers.
+    /// Which of them the inserted elements would have landed in is unknown, so
+    /// this is an approximation: a register with value `v > k` becomes `2^k`
+    /// registers with value `v - k`, the expected max over a `2^k`-th of its
+    /// elements. A register with value `v <= k` likely holds very few elements,
+    /// so only the first of its `2^k` registers keeps `v` and the rest are zero.
+    /// The count is approximately preserved, but further inserts and unions
+    /// with sketches built at `new_precision` are not exact.
+    ///
+    /// Returns `Err(Error::InvalidPrecision)` if `new_precision` is not in
+    /// [`PRECISION_RANGE`].
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLog;
+    ///
+    /// let mut hll = HyperLogLog::seeded(10, 42);
+    /// hll.extend(0..100_000);
+    ///
+    /// let up = hll.rotate_precision(12).unwrap();
+    /// assert_eq!(up.precision(), 12);
+    /// assert!((up.estimate() - hll.estimate()).abs() < 0.05 * hll.estimate());
+    ///
+    /// let down = hll.rotate_precision(8).unwrap();
+    /// let mut control = HyperLogLog::seeded(8, 42);
+    /// control.extend(0..100_000);
+    /// assert_eq!(down, control);
+    /// ```
+    pub fn rotate_precision(&self, new_precision: u8) -> Result<Self, Error> {
+        if !PRECISION_RANGE.contains(&new_precision) {
+            return Err(Error::InvalidPrecision);
+        }
+        if new_precision <= self.precision() {
+            let mut res = self.clone();
+            res.downgrade_precision(new_precision)?;
+            return Ok(res);
+        }
+        let k = new_precision - self.precision();
+        let registers: Vec<u8> = self
+            .iter()
+            .flat_map(|v| {
+                let (first, rest) = match v > k {
+                    true => (v - k, v - k),
+                    false => (v, 0),
+                };
+                core::iter::once(first).chain(repeat(rest).take((1 << k) - 1))
+            })
+            .collect();
+        let len = registers.len();
+        let mut res = Self {
+            hasher: self.hasher.clone(),
+            precision: new_precision as u32,
+            zeros: 0,
+            correction: correction(len),
+            registers: registers.into_boxed_slice(),
+            sum: 0.0,
+            updated_count: true,
+            serde_version: SerdeVersion,
+        };
+        res.recompute_sum_and_zeros();
+        Ok(res)
+    }
+}
+
+impl<S> HyperLogLog<S> {
+    /// Moves the registers and count state of `self` into a [`HyperLogLog`]
+    /// that uses `hasher`.
+    #[inline]
+    pub(crate) fn replace_hasher<H>(self, hasher: H) -> HyperLogLog<H> {
+        HyperLogLog {
+            hasher,
+            precision: self.precision,
+            zeros: self.zeros,
+            correction: self.correction,
+            registers: self.registers,
+            sum: self.sum,
+            updated_count: self.updated_count,
+            serde_version: SerdeVersion,
         }
     }
 }
@@ -385,23 +2191,37 @@ impl<T: Hash, S: BuildHasher> Extend<T> for HyperLogLog<S> {
     }
 }
 
-impl<S: BuildHasher> AtomicHyperLogLog<S> {
+impl<S: BuildHasher, const VERSIONED: bool> AtomicHyperLogLog<S, VERSIONED> {
     /// Returns an iterator over the value of each register.
     #[inline]
     pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
         self.registers.iter().map(|x| x.load(Relaxed))
     }
 
+    #[inline(always)]
+    fn register(&self, index: usize) -> u8 {
+        self.registers[index].load(Relaxed)
+    }
+
     /// Inserts the hash of an item into the HyperLogLog.
     #[inline(always)]
-    fn insert_inner<const UPDATE_COUNT: bool>(&self, hash: u64) {
+    fn insert_inner<const UPDATE_COUNT: bool>(&self, hash: u64) -> bool {
         let index = (hash >> (64 - self.precision)) as usize;
         let new = 1 + hash.trailing_zeros() as u8;
-        self.update::<UPDATE_COUNT>(new, index);
+        self.update::<UPDATE_COUNT>(new, index)
     }
 
+    /// Sets register `index` to `new` if larger, returning whether it changed.
     #[inline(always)]
-    fn update<const UPDATE_COUNT: bool>(&self, new: u8, index: usize) {
+    fn update<const UPDATE_COUNT: bool>(&self, new: u8, index: usize) -> bool {
+        // Registers only increase, so if this load is not less than `new`, the
+        // register won't change.
+        if VERSIONED && self.registers[index].load(Relaxed) < new {
+            // The release fence orders the version increment before the register
+            // change for readers that observe the change, see `try_union`.
+            self.version.fetch_add(1, Release);
+            fence(Release);
+        }
         let old = self.registers[index].fetch_max(new, Relaxed);
         if UPDATE_COUNT && self.updated_count() {
             if old < new {
@@ -412,6 +2232,16 @@ impl<S: BuildHasher> AtomicHyperLogLog<S> {
         } else {
             self.updated_count.store(false, Relaxed);
         }
+        new > old
+    }
+
+    /// Increments the version before registers may change, see `update`.
+    #[inline]
+    fn bump_version(&self) {
+        if VERSIONED {
+            self.version.fetch_add(1, Release);
+            fence(Release);
+        }
     }
 
     #[inline]
@@ -419,9 +2249,20 @@ impl<S: BuildHasher> AtomicHyperLogLog<S> {
         self.updated_count.load(Relaxed)
     }
 
-    /// Returns the approximate number of elements in `self`.
+    /// Returns the number of zero registers and the sum of `2^-register`,
+    /// recomputing them if the cached values are stale.
+    #[inline]
+    fn zeros_and_sum(&self) -> (usize, f64) {
+        match self.updated_count() {
+            true => (self.zeros.load(Relaxed), self.sum.load(Relaxed)),
+            false => self.zeros_and_sum_from_scratch(),
+        }
+    }
+
+    /// Returns the approximate number of elements in `self` as a float.
+    /// [`Self::count`] is this estimate rounded to an integer.
     #[inline]
-    pub fn raw_count(&self) -> f64 {
+    pub fn estimate(&self) -> f64 {
         match self.updated_count() {
             true => {
                 let zeros = self.zeros.load(Relaxed);
@@ -432,45 +2273,468 @@ impl<S: BuildHasher> AtomicHyperLogLog<S> {
         }
     }
 
-    /// Low level method to expose de/serializable parts of `self`.
-    pub fn parts<'a>(&'a self) -> (&'a [AtomicU8], &'a S, usize, f64, bool) {
-        (
-            &self.registers,
-            &self.hasher,
-            self.zeros.load(Relaxed),
-            self.sum.load(Relaxed),
-            self.updated_count.load(Relaxed),
-        )
+    /// Returns the same value as [`Self::estimate`], loading the count state
+    /// (or the registers, if the count state is stale) with `ordering` instead
+    /// of `Relaxed`.
+    ///
+    /// [`Self::estimate`] makes no ordering guarantees with respect to other
+    /// memory. Use `Acquire` here to pair with a `Release` store or fence made
+    /// by the inserting thread after its inserts, so that the estimate reflects
+    /// at least the inserts that happened before it, without paying for `SeqCst`
+    /// on every count.
+    ///
+    /// # Panics
+    /// Panics if `ordering` is `Release` or `AcqRel`, like [`AtomicU64::load`].
+    ///
+    /// # Example
+    /// ```
+    /// use core::sync::atomic::Ordering;
+    /// use hyperloglockless::AtomicHyperLogLog;
+    ///
+    /// let hll = AtomicHyperLogLog::seeded(12, 42);
+    /// hll.extend(0..1000);
+    /// assert_eq!(hll.estimate_with_ordering(Ordering::Acquire), hll.estimate());
+    /// ```
+    pub fn estimate_with_ordering(&self, ordering: Ordering) -> f64 {
+        match self.updated_count.load(ordering) {
+            true => {
+                let zeros = self.zeros.load(ordering);
+                let sum = self.sum.load(ordering);
+                self.raw_count_inner(zeros, sum)
+            }
+            false => {
+                let registers = self.registers.iter().map(|r| r.load(ordering));
+                let (zeros, sum) = zeros_and_sum_of(registers);
+                self.raw_count_inner(zeros, sum)
+            }
+        }
+    }
+
+    /// Returns [`Self::estimate`] computed with `f32` arithmetic from the count
+    /// state, loaded with `Relaxed`, for platforms where `f32` math is cheaper.
+    /// Unlike [`Self::count_f32`], which rounds the `f64` estimate, every step
+    /// but the LogLog-Beta bias term is computed in `f32`. If the count state
+    /// is stale, the registers are scanned as in [`Self::estimate`].
+    ///
+    /// `f32` has a 24-bit mantissa, so the sum of `2^-register` and the
+    /// `m * (m - zeros)` numerator are each rounded to a relative precision of
+    /// about `6e-8`. The result is within about `1e-6` of the `f64` estimate,
+    /// relative, at every precision: negligible next to the estimate's own
+    /// error of `1.04 / sqrt(m)`.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::AtomicHyperLogLog;
+    ///
+    /// let hll = AtomicHyperLogLog::seeded(14, 42);
+    /// hll.extend(0..100_000);
+    /// let estimate = hll.estimate() as f32;
+    /// assert!((hll.count_atomic_f32() - estimate).abs() <= 1e-6 * estimate);
+    /// ```
+    pub fn count_atomic_f32(&self) -> f32 {
+        let (zeros, sum) = self.zeros_and_sum();
+        let m = self.len() as f32;
+        let d = sum as f32 + beta_horner(zeros, self.precision) as f32;
+        self.correction as f32 * (m * (m - zeros as f32)) / d
+    }
+
+    /// Returns the approximate standard error of [`Self::count_atomic_f32`], in
+    /// elements: the count times the relative error [`error_for_precision`],
+    /// computed in `f32`. The true count is within one standard error of the
+    /// estimate about 68% of the time, and within two about 95% of the time.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::AtomicHyperLogLog;
+    ///
+    /// let hll = AtomicHyperLogLog::seeded(14, 42);
+    /// hll.extend(0..100_000);
+    /// let error = hll.estimate_error_f32();
+    /// assert!((error - 810.0).abs() < 50.0);
+    /// assert!((hll.count_atomic_f32() - 100_000.0).abs() < 3.0 * error);
+    /// ```
+    pub fn estimate_error_f32(&self) -> f32 {
+        self.count_atomic_f32() * error_for_precision(self.precision()) as f32
+    }
+
+    /// Returns a copy of the register values of `self`, e.g. to serialize or
+    /// send them elsewhere. Cheaper than converting a clone into a
+    /// [`HyperLogLog`] since the count state is not copied or recomputed.
+    ///
+    /// A `SeqCst` fence is issued before the registers are loaded with
+    /// `Relaxed`. Inserts don't issue a matching fence, so this gives no
+    /// stronger guarantee than [`Self::iter`]: inserts are only guaranteed to be
+    /// included if they happen before the call through other synchronization,
+    /// e.g. joining the inserting thread. Inserts concurrent with the call may
+    /// or may not be included, per register: the snapshot is not atomic as a
+    /// whole.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::{AtomicHyperLogLog, HyperLogLog};
+    ///
+    /// let hll = AtomicHyperLogLog::seeded(12, 42);
+    /// hll.extend(0..1000);
+    ///
+    /// let registers = hll.load_registers_snapshot();
+    /// let mut other = HyperLogLog::seeded(12, 42);
+    /// other.union_from_bytes(&registers).unwrap();
+    /// assert_eq!(other.count(), hll.count());
+    /// ```
+    pub fn load_registers_snapshot(&self) -> Vec<u8> {
+        fence(Ordering::SeqCst);
+        self.registers.iter().map(|r| r.load(Relaxed)).collect()
+    }
+
+    /// Overwrites the registers and count state of `self` with those of
+    /// `source`, e.g. to reset a shared sketch to a baseline restored from a
+    /// snapshot. Unlike [`Self::union`], registers can decrease.
+    ///
+    /// Each register is stored with `SeqCst`, then the count state is copied.
+    /// This is not safe while other threads insert into `self`: an insert
+    /// between the stores can be lost, or leave the count state inconsistent
+    /// with the registers. If `self` is a [`VersionedAtomicHyperLogLog`],
+    /// concurrent [`Self::try_union`]s with `self` as the source report
+    /// `Err(Error::ConcurrentModification)`.
+    ///
+    /// Returns `Err(Error::IncompatibleLength)` if the two HyperLogLogs have
+    /// different length ([`Self::len`]). This does not verify that the HLLs use
+    /// the same hasher or seed.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::{AtomicHyperLogLog, HyperLogLog};
+    ///
+    /// let mut baseline = HyperLogLog::seeded(12, 42);
+    /// baseline.extend(0..100);
+    ///
+    /// let hll = AtomicHyperLogLog::seeded(12, 42);
+    /// hll.extend(0..10_000);
+    /// hll.sync_from(&baseline).unwrap();
+    /// assert_eq!(hll.count(), baseline.count());
+    /// ```
+    pub fn sync_from(&self, source: &HyperLogLog<S>) -> Result<(), Error> {
+        if self.len() != source.len() {
+            return Err(Error::IncompatibleLength);
+        }
+        self.bump_version();
+        for (register, value) in core::iter::zip(self.registers.iter(), source.iter()) {
+            register.store(value, Ordering::SeqCst);
+        }
+        let (_, _, zeros, sum, updated_count) = source.parts();
+        self.zeros.store(zeros, Ordering::SeqCst);
+        self.sum.store(sum, Ordering::SeqCst);
+        self.updated_count.store(updated_count, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Low level method to expose de/serializable parts of `self`.
+    pub fn parts(&self) -> (&[AtomicU8], &S, usize, f64, bool) {
+        (
+            &self.registers,
+            &self.hasher,
+            self.zeros.load(Relaxed),
+            self.sum.load(Relaxed),
+            self.updated_count.load(Relaxed),
+        )
+    }
+
+    /// Recomputes the cached count state of `self` by scanning all registers,
+    /// making the next call to [`Self::count`] O(1) again.
+    ///
+    /// This should not be called concurrently with inserts into `self`, as
+    /// their count updates may be lost.
+    pub fn recompute_sum_and_zeros(&self) {
+        let (zeros, sum) = self.zeros_and_sum_from_scratch();
+        self.zeros.store(zeros, Relaxed);
+        self.sum.store(sum, Relaxed);
+        self.updated_count.store(true, Relaxed);
+    }
+
+    /// Merges the registers of `source` into `self` and then recomputes the count
+    /// state once, which is faster than [`Self::union`] for restoring many
+    /// registers, e.g. from a checkpoint. Returns `Err(Error::IncompatibleLength)`
+    /// if the two HyperLogLogs have different length ([`Self::len`]).
+    ///
+    /// The registers are merged correctly even with concurrent inserts, but this
+    /// is not safe to call concurrently with inserts into `self`, as their count
+    /// updates may be lost. See [`Self::recompute_sum_and_zeros`].
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::{AtomicHyperLogLog, HyperLogLog};
+    ///
+    /// let mut checkpoint = HyperLogLog::seeded(12, 42);
+    /// checkpoint.extend(0..1000);
+    /// let hll = AtomicHyperLogLog::seeded(12, 42);
+    /// hll.bulk_import(&checkpoint).unwrap();
+    /// assert_eq!(hll.estimate(), checkpoint.estimate());
+    /// ```
+    pub fn bulk_import(&self, source: &HyperLogLog<S>) -> Result<(), Error> {
+        if self.len() != source.len() {
+            return Err(Error::IncompatibleLength);
+        }
+        self.bump_version();
+        for (register, x) in core::iter::zip(self.registers.iter(), source.iter()) {
+            register.fetch_max(x, Relaxed);
+        }
+        self.recompute_sum_and_zeros();
+        Ok(())
+    }
+
+    /// Like [`Self::union`], but returns `Err(Error::ConcurrentModification)` if
+    /// a concurrent change to `other` was observed during the merge, i.e. the
+    /// merge may have read a mix of registers from before and after the change.
+    ///
+    /// If `Ok(())` is returned, the merged registers of `other` are a state
+    /// that `other` actually passed through. On error, `self` still contains
+    /// the (possibly mixed) registers of `other`, which is not incorrect, as
+    /// they're a subset of the registers of `other` after the concurrent change.
+    /// Since merging is idempotent, it is safe to simply retry.
+    ///
+    /// Changes to `other` can only be detected if it tracks a version, so it
+    /// must be a [`VersionedAtomicHyperLogLog`], see [`Self::into_versioned`].
+    ///
+    /// Returns `Err(Error::IncompatibleLength)` if the two HyperLogLogs have
+    /// different length ([`Self::len`]).
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::AtomicHyperLogLog;
+    ///
+    /// let hll = AtomicHyperLogLog::seeded(12, 42);
+    /// let other = AtomicHyperLogLog::seeded(12, 42).into_versioned();
+    /// other.extend(0..1000);
+    /// while hll.try_union(&other).is_err() {}
+    /// assert!(hll.iter().eq(other.iter()));
+    /// ```
+    pub fn try_union(&self, other: &VersionedAtomicHyperLogLog<S>) -> Result<(), Error> {
+        if self.len() != other.len() {
+            return Err(Error::IncompatibleLength);
+        }
+        let before = other.version.load(Acquire);
+        if self.updated_count() {
+            other.iter().enumerate().for_each(|(i, x)| {
+                self.update::<true>(x, i);
+            });
+        } else {
+            other.iter().enumerate().for_each(|(i, x)| {
+                self.update::<false>(x, i);
+            });
+        }
+        // Any register change read above is preceded by an increment of
+        // `other.version` made visible by this fence.
+        fence(Acquire);
+        match other.version.load(Relaxed) == before {
+            true => Ok(()),
+            false => Err(Error::ConcurrentModification),
+        }
+    }
+
+    /// Inserts all the items in `iter` into the `self`.
+    #[inline]
+    pub fn extend<T: Hash, I: IntoIterator<Item = T>>(&self, iter: I) {
+        for val in iter {
+            self.insert(&val);
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<S: BuildHasher> HyperLogLog<S> {
+    /// Like [`Self::recompute_sum_and_zeros`], but scans the registers in
+    /// parallel using rayon.
+    ///
+    /// The registers are split into chunks, one per rayon worker, whose
+    /// partial zero counts and sums are then combined. This only pays off for
+    /// high precisions, where the serial scan is the bottleneck.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLog;
+    ///
+    /// let mut hll = HyperLogLog::seeded(18, 42);
+    /// hll.insert_lazy(&42);
+    /// hll.recompute_sum_and_zeros_par();
+    /// assert_eq!(hll.count(), 1);
+    /// ```
+    pub fn recompute_sum_and_zeros_par(&mut self) {
+        use rayon::prelude::*;
+        let threads = rayon::current_num_threads();
+        let chunk_size = ((self.registers.len() + threads - 1) / threads).max(1);
+        (self.zeros, self.sum) = self
+            .registers
+            .par_chunks(chunk_size)
+            .map(|chunk| {
+                chunk.iter().fold((0, 0.0), |(zeros, sum), &x| {
+                    (zeros + (x == 0) as usize, sum + INV_POW2[x as usize])
+                })
+            })
+            .reduce(|| (0, 0.0), |(z1, s1), (z2, s2)| (z1 + z2, s1 + s2));
+        self.updated_count = true;
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<S: BuildHasher + Clone + Send + Sync> HyperLogLog<S> {
+    /// Returns a new [`HyperLogLog`] with `1 << precision` registers and the
+    /// provided hasher, containing every item of `data`, inserted in parallel
+    /// using rayon.
+    ///
+    /// `data` is split into chunks, one per rayon worker. Each chunk is
+    /// inserted into its own [`HyperLogLog`] with a clone of `hasher`, and the
+    /// per-chunk sketches are then merged with [`Self::merge_into`]. The
+    /// result is identical to inserting `data` serially.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::{DefaultHasher, HyperLogLog};
+    ///
+    /// let data: Vec<u64> = (0..100_000).collect();
+    /// let hasher = DefaultHasher::seeded(&[42; 16]);
+    /// let hll = HyperLogLog::insert_all_parallel(&data, 12, hasher.clone());
+    ///
+    /// let mut control = HyperLogLog::with_hasher(12, hasher);
+    /// control.extend(&data);
+    /// assert_eq!(hll, control);
+    /// ```
+    pub fn insert_all_parallel<T: Hash + Send + Sync>(data: &[T], precision: u8, hasher: S) -> Self {
+        use rayon::prelude::*;
+        let threads = rayon::current_num_threads();
+        let chunk_size = ((data.len() + threads - 1) / threads).max(1);
+        data.par_chunks(chunk_size)
+            .map(|chunk| {
+                let mut hll = Self::with_hasher(precision, hasher.clone());
+                hll.extend(chunk);
+                hll
+            })
+            .reduce(
+                || Self::with_hasher(precision, hasher.clone()),
+                |mut left, right| {
+                    left.merge_into(right).expect("same precision");
+                    left
+                },
+            )
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<S: BuildHasher + Sync, const VERSIONED: bool> AtomicHyperLogLog<S, VERSIONED> {
+    /// Merges many [`HyperLogLog`]s into `self` in parallel using rayon,
+    /// updating the count.
+    ///
+    /// `others` is split into chunks, one per rayon worker, and each worker
+    /// merges its chunk into `self` serially. Returns
+    /// `Err(Error::IncompatibleLength)` without modifying `self` if any of
+    /// `others` has a different length ([`Self::len`]).
+    ///
+    /// Like [`Self::union`], this does not verify that the HLLs use the same
+    /// hasher or seed.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::{AtomicHyperLogLog, HyperLogLog};
+    ///
+    /// let hll = AtomicHyperLogLog::seeded(12, 42);
+    /// let others: Vec<_> = (0..8)
+    ///     .map(|i| {
+    ///         let mut other = HyperLogLog::seeded(12, 42);
+    ///         other.extend(i * 100..(i + 1) * 100);
+    ///         other
+    ///     })
+    ///     .collect();
+    /// hll.par_union_many(&others).unwrap();
+    /// ```
+    pub fn par_union_many(&self, others: &[HyperLogLog<S>]) -> Result<(), Error> {
+        use rayon::prelude::*;
+        if others.iter().any(|other| other.len() != self.len()) {
+            return Err(Error::IncompatibleLength);
+        }
+        let threads = rayon::current_num_threads();
+        let chunk_size = ((others.len() + threads - 1) / threads).max(1);
+        others
+            .par_chunks(chunk_size)
+            .for_each(|chunk| chunk.iter().for_each(|other| self.union_registers(other)));
+        Ok(())
     }
 
-    /// Low level method to construct [`Self`] de/serializable parts.
-    pub fn from_parts(registers: Box<[AtomicU8]>, hasher: S, zeros: usize, sum: f64, updated_count: bool) -> Self {
-        let len = registers.len() as u64;
-        let precision = len.trailing_zeros();
-        assert_eq!(precision + len.leading_zeros(), 63, "resigers.len() not a power of 2");
-        assert_eq!(1 << precision, registers.len());
-        validate_precision(precision as u8);
-        Self {
-            hasher,
-            precision,
-            zeros: AtomicUsize::new(zeros),
-            correction: correction(registers.len()),
-            registers,
-            sum: AtomicF64::new(sum),
-            updated_count: AtomicBool::new(updated_count),
+    /// Merges many [`HyperLogLog`]s into `self` in parallel using rayon,
+    /// updating the count. Returns `Err(Error::IncompatibleLength)` without
+    /// modifying `self` if any of `sources` has a different length
+    /// ([`Self::len`]).
+    ///
+    /// Unlike [`Self::par_union_many`], which splits `sources` across workers,
+    /// this splits the registers: each worker takes a chunk of 64 registers (one
+    /// cache line of `self`), computes the max of that chunk over all `sources`
+    /// in a local buffer, and stores it into `self` once. Each atomic register
+    /// is written at most once instead of once per source, so this is faster
+    /// when there are many sources.
+    ///
+    /// Like [`Self::union`], this does not verify that the HLLs use the same
+    /// hasher or seed.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::{AtomicHyperLogLog, HyperLogLog};
+    ///
+    /// let hll = AtomicHyperLogLog::seeded(12, 42);
+    /// let sources: Vec<_> = (0..8)
+    ///     .map(|i| {
+    ///         let mut source = HyperLogLog::seeded(12, 42);
+    ///         source.extend(i * 100..(i + 1) * 100);
+    ///         source
+    ///     })
+    ///     .collect();
+    /// hll.par_union_by_registers(&sources).unwrap();
+    ///
+    /// let control = AtomicHyperLogLog::seeded(12, 42);
+    /// control.extend(0..800);
+    /// assert_eq!(hll, control);
+    /// ```
+    pub fn par_union_by_registers(&self, sources: &[HyperLogLog<S>]) -> Result<(), Error> {
+        use rayon::prelude::*;
+        const CHUNK: usize = 64;
+        if sources.iter().any(|source| source.len() != self.len()) {
+            return Err(Error::IncompatibleLength);
         }
+        let updated_count = self.updated_count();
+        self.registers.par_chunks(CHUNK).enumerate().for_each(|(c, chunk)| {
+            let start = c * CHUNK;
+            let mut max = [0u8; CHUNK];
+            let max = &mut max[..chunk.len()];
+            for source in sources {
+                let registers = &source.registers[start..start + max.len()];
+                for (m, &r) in max.iter_mut().zip(registers) {
+                    *m = (*m).max(r);
+                }
+            }
+            for (i, &m) in max.iter().enumerate() {
+                match updated_count {
+                    true => self.update::<true>(m, start + i),
+                    false => self.update::<false>(m, start + i),
+                };
+            }
+        });
+        Ok(())
     }
 
-    /// Inserts all the items in `iter` into the `self`.
+    /// Merges the registers of a [`HyperLogLog`] into `self`. Assumes equal
+    /// length.
     #[inline]
-    pub fn extend<T: Hash, I: IntoIterator<Item = T>>(&self, iter: I) {
-        for val in iter {
-            self.insert(&val);
+    fn union_registers(&self, other: &HyperLogLog<S>) {
+        if self.updated_count() {
+            other.iter().enumerate().for_each(|(i, x)| {
+                self.update::<true>(x, i);
+            });
+        } else {
+            other.iter().enumerate().for_each(|(i, x)| {
+                self.update::<false>(x, i);
+            });
         }
     }
 }
 
-impl<S: BuildHasher + Clone> Clone for AtomicHyperLogLog<S> {
+impl<S: BuildHasher + Clone, const VERSIONED: bool> Clone for AtomicHyperLogLog<S, VERSIONED> {
     fn clone(&self) -> Self {
         Self {
             hasher: self.hasher.clone(),
@@ -480,15 +2744,74 @@ impl<S: BuildHasher + Clone> Clone for AtomicHyperLogLog<S> {
             registers: self.iter().map(AtomicU8::new).collect::<Vec<_>>().into(),
             sum: AtomicF64::new(self.sum.load(Relaxed)),
             updated_count: self.updated_count.load(Relaxed).into(),
+            serde_version: SerdeVersion,
+            version: AtomicU64::new(self.version.load(Relaxed)),
+        }
+    }
+}
+
+impl<S, const VERSIONED: bool> From<AtomicHyperLogLog<S, VERSIONED>> for HyperLogLog<S> {
+    /// Converts an [`AtomicHyperLogLog`] into a [`HyperLogLog`] with the same
+    /// registers, hasher, and count state.
+    fn from(hll: AtomicHyperLogLog<S, VERSIONED>) -> Self {
+        Self {
+            registers: hll.registers.iter().map(|x| x.load(Relaxed)).collect::<Vec<_>>().into(),
+            precision: hll.precision,
+            hasher: hll.hasher,
+            zeros: hll.zeros.load(Relaxed),
+            sum: hll.sum.load(Relaxed),
+            correction: hll.correction,
+            updated_count: hll.updated_count.load(Relaxed),
+            serde_version: SerdeVersion,
         }
     }
 }
 
+#[cfg(target_has_atomic = "ptr")]
+impl<S, const VERSIONED: bool> AtomicHyperLogLog<S, VERSIONED> {
+    /// Converts the [`AtomicHyperLogLog`] in `arc` into a [`HyperLogLog`] if
+    /// `arc` is the only strong reference to it, e.g. once all writers are done.
+    /// Otherwise `arc` is returned unchanged.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::AtomicHyperLogLog;
+    /// use std::sync::Arc;
+    ///
+    /// let hll = Arc::new(AtomicHyperLogLog::seeded(12, 42));
+    /// let writer = Arc::clone(&hll);
+    /// std::thread::spawn(move || writer.insert(&42)).join().unwrap();
+    ///
+    /// let hll = AtomicHyperLogLog::drain_into(hll).unwrap();
+    /// assert_eq!(hll.count(), 1);
+    /// ```
+    pub fn drain_into(arc: alloc::sync::Arc<Self>) -> Result<HyperLogLog<S>, alloc::sync::Arc<Self>> {
+        alloc::sync::Arc::try_unwrap(arc).map(HyperLogLog::from)
+    }
+}
+
+/// The supported precisions of [`HyperLogLog`] and [`AtomicHyperLogLog`], and
+/// the precisions that [`HyperLogLogPlus`] switches to in dense mode.
+///
+/// # Example
+/// ```
+/// use hyperloglockless::{HyperLogLog, PRECISION_RANGE};
+///
+/// for precision in PRECISION_RANGE {
+///     assert_eq!(HyperLogLog::new(precision).precision(), precision);
+/// }
+/// ```
+pub const PRECISION_RANGE: core::ops::RangeInclusive<u8> = 4..=18;
+
 #[inline]
 fn validate_precision(precision: u8) {
-    assert!((4..=18).contains(&precision), "Precisions 4..=18 supported only.");
+    assert!(
+        PRECISION_RANGE.contains(&precision),
+        "Precisions 4..=18 supported only."
+    );
 }
 
+#[allow(clippy::excessive_precision)]
 static INV_POW2: [f64; 66] = [
     1.0,
     0.5,
@@ -559,7 +2882,7 @@ static INV_POW2: [f64; 66] = [
 ];
 
 /// Returns the HyperLogLog precision that will have the error for calls to
-/// `count` and `raw_count`.
+/// `count` and `estimate`.
 #[inline]
 pub fn precision_for_error(error: f64) -> u8 {
     assert!(0.0 < error && error < 1.0);
@@ -567,7 +2890,7 @@ pub fn precision_for_error(error: f64) -> u8 {
     ceil(log2(pow(bias_constant / error, 2.0))) as u8
 }
 
-/// Returns the approximate error of `count` and `raw_count` given the precision
+/// Returns the approximate error of `count` and `estimate` given the precision
 /// of a [`HyperLogLog`] or [`AtomicHyperLogLog`].
 #[inline]
 pub fn error_for_precision(precision: u8) -> f64 {
@@ -576,6 +2899,14 @@ pub fn error_for_precision(precision: u8) -> f64 {
     bias_constant / sqrt((1u64 << precision) as f64)
 }
 
+/// Returns the estimate of a HyperLogLog with the given precision and every
+/// register at `64 - precision`. See `theoretical_max_count`.
+fn theoretical_max_count_of(precision: u32) -> f64 {
+    let m = 1usize << precision;
+    let sum = m as f64 * INV_POW2[64 - precision as usize];
+    correction(m) * (m * m) as f64 / (sum + beta_horner(0, precision))
+}
+
 #[inline(always)]
 fn correction(count: usize) -> f64 {
     // Hardcoded since the result of f64::ln varies by platform
@@ -597,8 +2928,39 @@ pub(crate) fn hash_one<S: BuildHasher, T: Hash + ?Sized>(hasher: &S, value: &T)
     h.finish()
 }
 
+/// Returns the number of registers with each value in `registers`.
+#[inline]
+fn histogram_of(registers: impl Iterator<Item = u8>) -> [usize; 66] {
+    let mut data = [0usize; 66];
+    for r in registers {
+        data[r as usize] += 1;
+    }
+    data
+}
+
+/// Returns the number of zero registers and the sum of `2^-register` over
+/// `registers`.
+fn zeros_and_sum_of(registers: impl Iterator<Item = u8>) -> (usize, f64) {
+    let data = histogram_of(registers);
+    let zeros = data[0];
+    let mut sum = zeros as f64;
+    for i in 1..=65 {
+        sum += data[i] as f64 * INV_POW2[i];
+    }
+    (zeros, sum)
+}
+
+/// The splitmix64 finalizer, a fast bijective mix of all 64 bits of `x`.
+#[inline(always)]
+pub(crate) fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
 macro_rules! impl_tests {
-    ($modname:ident, $name:ident, $seed:literal) => {
+    ($modname:ident, $name:ident, $other:ident, $seed:literal) => {
         #[allow(unused_mut)]
         #[cfg(not(feature = "loom"))]
         #[cfg(test)]
@@ -638,12 +3000,12 @@ macro_rules! impl_tests {
                         hll.insert_hash(hash);
                         if x % 10 == 0 {
                             let real = x as f64;
-                            let diff = hll.raw_count() - real;
+                            let diff = hll.estimate() - real;
                             total_err += diff.abs() / real;
                             total_diff += diff / real;
                             counted += 1;
                             if x % 10000 == 0 {
-                                assert_eq!(hll.raw_count(), hll.count_from_scratch());
+                                assert_eq!(hll.estimate(), hll.count_from_scratch());
                             }
                         }
                     }
@@ -699,7 +3061,8 @@ macro_rules! impl_tests {
                                         control.extend(ri..rj);
 
                                         left.union(&right).unwrap();
-                                        assert_eq!(left.raw_count(), control.raw_count());
+                                        left.assert_invariants();
+                                        assert_eq!(left.estimate(), control.estimate());
                                         assert_eq!(left, control);
                                     }
                                 }
@@ -709,54 +3072,1161 @@ macro_rules! impl_tests {
                 }
             }
 
-            #[cfg(feature = "serde")]
+            #[cfg(feature = "serde")]
+            #[test]
+            fn test_serde() {
+                for precision in 4..=18 {
+                    let mut before = $name::seeded(precision, $seed);
+                    before.extend(0..=1000);
+
+                    let s = serde_cbor::to_vec(&before).unwrap();
+                    let mut after: $name = serde_cbor::from_slice(&s).unwrap();
+                    assert_eq!(before, after);
+
+                    before.extend(1000..=2000);
+                    after.extend(1000..=2000);
+                    assert_eq!(before, after);
+                }
+            }
+
+            #[cfg(feature = "cbor")]
+            #[test]
+            fn test_cbor_bytes_parity() {
+                for precision in [4, 10, 18] {
+                    let mut hll = $name::seeded(precision, $seed);
+                    hll.extend(0..=1000);
+
+                    let bytes = hll.to_cbor_bytes().unwrap();
+                    assert_eq!(serde_cbor::from_slice::<$name>(&bytes).unwrap(), hll);
+                    assert_eq!(
+                        $name::from_cbor_bytes(&serde_cbor::to_vec(&hll).unwrap()).unwrap(),
+                        hll
+                    );
+                    assert_eq!($name::from_cbor_bytes(&bytes).unwrap(), hll);
+
+                    let mut other: $other = $other::from_cbor_bytes(&bytes).unwrap();
+                    assert_eq!(other.to_cbor_bytes().unwrap(), bytes);
+                    let mut back: $name = $name::from_cbor_bytes(&other.to_cbor_bytes().unwrap()).unwrap();
+                    assert_eq!(back, hll);
+
+                    hll.extend(1000..=2000);
+                    other.extend(1000..=2000);
+                    back.extend(1000..=2000);
+                    assert_eq!(other.estimate(), hll.estimate());
+                    assert_eq!(back, hll);
+                }
+                assert_eq!(
+                    $name::<DefaultHasher>::from_cbor_bytes(&[0xFF]),
+                    Err(Error::InvalidEncoding)
+                );
+            }
+
+            /// The serialized forms of both types are interchangeable.
+            #[cfg(feature = "serde")]
+            #[test]
+            fn test_serde_parity() {
+                for precision in 4..=18 {
+                    let mut hll = $name::seeded(precision, $seed);
+                    hll.extend(0..=1000);
+                    let mut other = $other::seeded(precision, $seed);
+                    other.extend(0..=1000);
+
+                    let bytes = serde_cbor::to_vec(&hll).unwrap();
+                    assert_eq!(bytes, serde_cbor::to_vec(&other).unwrap());
+
+                    let mut from_other: $name = serde_cbor::from_slice(&bytes).unwrap();
+                    let mut other_from: $other = serde_cbor::from_slice(&bytes).unwrap();
+                    assert_eq!(from_other, hll);
+                    assert_eq!(other_from, other);
+
+                    hll.extend(1000..=2000);
+                    from_other.extend(1000..=2000);
+                    other_from.extend(1000..=2000);
+                    assert_eq!(from_other, hll);
+                    assert_eq!(other_from.count(), hll.count());
+                    assert_eq!(other_from.estimate(), hll.estimate());
+                }
+            }
+
+            #[test]
+            fn test_error_helpers() {
+                for precision in 4..=18 {
+                    let err = error_for_precision(precision);
+                    let prec = precision_for_error(err);
+                    assert_eq!(prec, precision);
+                }
+            }
+
+            #[test]
+            fn test_count_updated() {
+                for precision in 4..=18 {
+                    let mut hll = $name::seeded(precision, $seed);
+                    assert!(hll.updated_count());
+                    hll.insert_lazy(&42);
+                    let _ = hll.count();
+                    assert!(!hll.updated_count());
+                }
+            }
+
+            #[cfg(not(feature = "verbose_debug"))]
+            #[test]
+            fn test_debug() {
+                let mut hll = $name::seeded(4, $seed);
+                hll.insert(&1);
+                let s = alloc::format!("{:?}", hll);
+                let prefix = concat!(
+                    stringify!($name),
+                    " { precision: 4, count: ~1, fill_factor: 0.0625, hasher: "
+                );
+                assert!(s.starts_with(prefix), "{}", s);
+            }
+
+            #[test]
+            fn test_max_register_value() {
+                let mut hll = $name::seeded(8, $seed);
+                assert_eq!(hll.max_register_value(), 0);
+                hll.insert_hash(0b1000);
+                assert_eq!(hll.max_register_value(), 4);
+                hll.insert_hash(0b10);
+                assert_eq!(hll.max_register_value(), 4);
+                hll.insert_hash(1 << 40);
+                assert_eq!(hll.max_register_value(), 41);
+            }
+
+            #[test]
+            fn test_weighted_insert() {
+                let mut hll = $name::seeded(14, $seed);
+                for x in 0..1000 {
+                    hll.weighted_insert(&x, 10);
+                }
+                let count = hll.estimate();
+                let err = (count - 10_000.0).abs() / 10_000.0;
+                assert!(err < 3.0 * error_for_precision(14), "{}", count);
+
+                for x in 0..1000 {
+                    hll.weighted_insert(&x, 10);
+                }
+                assert_eq!(hll.estimate(), count);
+                hll.weighted_insert(&0, 0);
+                assert_eq!(hll.estimate(), count);
+            }
+
+            #[test]
+            fn test_xor_distance() {
+                let mut left = $name::seeded(8, $seed);
+                let mut right = $name::seeded(8, $seed);
+                assert_eq!(left.xor_distance(&right), Ok(0));
+                left.insert_hash(0b1000); // register 0 = 4
+                right.insert_hash(0b10); // register 0 = 2
+                assert_eq!(left.xor_distance(&right), Ok(2));
+                right.insert_hash(u64::MAX); // register 255 = 1
+                assert_eq!(left.xor_distance(&right), Ok(3));
+                assert_eq!(right.xor_distance(&left), Ok(3));
+                assert_eq!(
+                    left.xor_distance(&$name::seeded(9, $seed)),
+                    Err(Error::IncompatibleLength)
+                );
+            }
+
+            #[test]
+            #[allow(deprecated)]
+            fn test_raw_count_alias() {
+                let mut hll = $name::seeded(12, $seed);
+                hll.extend(0..1000);
+                assert_eq!(hll.raw_count(), hll.estimate());
+            }
+
+            #[test]
+            fn test_count_f32() {
+                for precision in [4, 12, 18] {
+                    let mut hll = $name::seeded(precision, $seed);
+                    assert_eq!(hll.count_f32(), 0.0);
+                    hll.extend(0..100_000);
+                    assert_eq!(hll.count_f32(), hll.estimate() as f32);
+                    let err = (hll.count_f32() as f64 - 100_000.0).abs() / 100_000.0;
+                    let bound = 1.04 / (hll.len() as f64).sqrt();
+                    assert!(err < 3.0 * bound, "{} {}", err, bound);
+                }
+            }
+
+            #[test]
+            fn test_insert_and_report() {
+                let mut hll = $name::seeded(10, $seed);
+                let mut control = $name::seeded(10, $seed);
+                for x in 0..10_000 {
+                    let before: Vec<u8> = hll.iter().collect();
+                    let changed = hll.insert_and_report(&x);
+                    control.insert(&x);
+                    assert_eq!(changed, !hll.iter().eq(before));
+                    assert!(!hll.insert_and_report(&x));
+                }
+                assert_eq!(hll, control);
+                assert_eq!(hll.estimate(), control.estimate());
+            }
+
+            #[test]
+            fn test_compatibility_check() {
+                for precision in 4..=18 {
+                    let mut hll = $name::seeded(precision, $seed);
+                    assert_eq!(hll.compatibility_check(&$name::seeded(precision, $seed)), Ok(()));
+                    let other = $name::seeded(18 - (precision - 4), $seed);
+                    let res = hll.compatibility_check(&other);
+                    assert_eq!(
+                        res.is_ok(),
+                        hll.union_from_bytes(&other.iter().collect::<Vec<_>>()).is_ok()
+                    );
+                    if let Err(e) = res {
+                        assert_eq!(
+                            (e.self_precision, e.other_precision),
+                            (precision, other.precision())
+                        );
+                        assert_eq!((e.self_len, e.other_len), (hll.len(), other.len()));
+                    }
+                }
+            }
+
+            #[test]
+            fn test_insert_u64() {
+                for precision in [4, 12, 18] {
+                    let mut hll = $name::seeded(precision, $seed);
+                    let mut small = $name::seeded(precision, $seed);
+                    let mut control = $name::seeded(precision, $seed);
+                    for x in 0..100_000u32 {
+                        hll.insert_u64(x as u64);
+                        small.insert_u32(x);
+                        control.insert_hash(splitmix64(x as u64));
+                    }
+                    assert_eq!(hll, control);
+                    assert_eq!(small, control);
+                    assert_eq!(hll.estimate(), control.estimate());
+                    let err = (hll.estimate() - 100_000.0).abs() / 100_000.0;
+                    assert!(err < 3.0 * 1.04 / (hll.len() as f64).sqrt());
+                }
+            }
+
+            #[test]
+            fn test_union_from_bytes() {
+                for precision in [4, 10, 16] {
+                    let mut hll = $name::seeded(precision, $seed);
+                    let mut other = $name::seeded(precision, $seed);
+                    let mut control = $name::seeded(precision, $seed);
+                    hll.extend(0..1000);
+                    other.extend(500..2000);
+                    control.extend(0..2000);
+                    let bytes: Vec<u8> = other.iter().collect();
+                    hll.union_from_bytes(&bytes).unwrap();
+                    assert_eq!(hll, control);
+                    assert_eq!(hll.estimate(), control.estimate());
+                }
+                let mut hll = $name::seeded(4, $seed);
+                assert_eq!(
+                    hll.union_from_bytes(&[0; 15]),
+                    Err(Error::InvalidRegisterCount {
+                        expected: 16,
+                        got: 15
+                    })
+                );
+                let mut bytes = [0; 16];
+                bytes[3] = 66;
+                assert_eq!(hll.union_from_bytes(&bytes), Err(Error::InvalidEncoding));
+                assert_eq!(hll.count(), 0);
+            }
+
+            #[test]
+            fn test_sketch_distance() {
+                let mut left = $name::seeded(8, $seed);
+                let mut right = $name::seeded(8, $seed);
+                assert_eq!(left.sketch_distance(&right), Ok(0.0));
+                left.insert_hash(0b1000); // register 0 = 4
+                right.insert_hash(0b10); // register 0 = 2
+                right.insert_hash(u64::MAX); // register 255 = 1
+                assert_eq!(left.sketch_distance(&right), Ok(sqrt(5.0)));
+                assert_eq!(right.sketch_distance(&left), Ok(sqrt(5.0)));
+                left.extend(0..1000);
+                right.extend(0..1000);
+                assert!(left.sketch_distance(&right).unwrap() < sqrt(5.0));
+                assert_eq!(
+                    left.sketch_distance(&$name::seeded(9, $seed)),
+                    Err(Error::IncompatibleLength)
+                );
+            }
+
+            #[test]
+            fn test_count_geometric_mean() {
+                for precision in [10, 14] {
+                    let mut hll = $name::seeded(precision, $seed);
+                    hll.extend(0..1_000_000);
+                    let err = (hll.count_geometric_mean() - 1_000_000.0).abs() / 1_000_000.0;
+                    assert!(err < 0.1, "{}", hll.count_geometric_mean());
+                }
+            }
+
+            #[test]
+            fn test_register_histogram() {
+                let mut hll = $name::seeded(12, $seed);
+                let empty = hll.register_histogram();
+                assert_eq!(empty[0], hll.len());
+                assert_eq!(hll.expected_register_distribution()[0], 1.0);
+                for n in [1000, 10_000, 100_000, 1_000_000] {
+                    hll.extend(0..n);
+                    let histogram = hll.register_histogram();
+                    assert_eq!(histogram.iter().sum::<usize>(), hll.len());
+                    for (v, &count) in histogram.iter().enumerate() {
+                        assert_eq!(count, hll.iter().filter(|r| *r as usize == v).count());
+                    }
+                    let expected = hll.expected_register_distribution();
+                    assert!((expected.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+                    let m = hll.len() as f64;
+                    for v in 0..66 {
+                        assert!((histogram[v] as f64 / m - expected[v]).abs() < 0.02);
+                    }
+                }
+            }
+
+            #[test]
+            fn test_linear_count() {
+                for precision in [4, 10, 16] {
+                    let mut hll = $name::seeded(precision, $seed);
+                    assert_eq!(hll.linear_count(), 0.0);
+                    let n = hll.len() / 4;
+                    hll.extend(0..n);
+                    let m = hll.len() as f64;
+                    let zeros = hll.iter().filter(|r| *r == 0).count() as f64;
+                    assert_eq!(hll.linear_count(), m * ln(m / zeros));
+                    assert!((hll.linear_count() - n as f64).abs() < 0.2 * n as f64);
+                }
+                let mut hll = $name::seeded(4, $seed);
+                hll.extend(0..1000);
+                assert_eq!(hll.linear_count(), f64::INFINITY);
+            }
+
+            #[test]
+            fn test_theoretical_max_count() {
+                for precision in 4..=18 {
+                    let mut hll = $name::seeded(precision, $seed);
+                    let max = hll.theoretical_max_count();
+                    let shift = 64 - precision as u32;
+                    for i in 0..hll.len() as u64 {
+                        hll.insert_hash((i << shift) | (1 << (shift - 1)));
+                    }
+                    assert!(hll.iter().all(|r| r as u32 == shift));
+                    assert!((hll.count_from_scratch() - max).abs() <= max * 1e-9);
+                    assert!(max > 1e18);
+                }
+            }
+
+            #[test]
+            fn test_register_entropy() {
+                let mut hll = $name::seeded(12, $seed);
+                assert_eq!(hll.register_entropy(), 0.0);
+                hll.insert_hash(0b100);
+                let p = 1.0 / hll.len() as f64;
+                let expected = -p * ln(p) - (1.0 - p) * ln(1.0 - p);
+                assert!((hll.register_entropy() - expected).abs() < 1e-12);
+                for (n, lo, hi) in [(100_000, 1.8, 2.1), (10_000_000, 1.8, 2.1)] {
+                    let mut hll = $name::seeded(12, $seed);
+                    hll.extend(0..n);
+                    let entropy = hll.register_entropy();
+                    assert!(lo < entropy && entropy < hi, "{} {}", n, entropy);
+                }
+            }
+
+            #[test]
+            fn test_iter_top_k_registers() {
+                let mut hll = $name::seeded(8, $seed);
+                hll.extend(0..500);
+                let mut sorted: Vec<(usize, u8)> = hll.iter().enumerate().collect();
+                sorted.sort_by_key(|&(index, value)| (core::cmp::Reverse(value), index));
+                for k in [0, 1, 10, 255, 256, 1000] {
+                    let top: Vec<_> = hll.iter_top_k_registers(k).collect();
+                    assert_eq!(top, sorted[..k.min(hll.len())]);
+                }
+            }
+
+            #[test]
+            fn test_count_with_bootstrap_ci() {
+                let hll = $name::seeded(10, $seed);
+                assert_eq!(hll.count_with_bootstrap_ci(0.95), (0.0, 0.0, 0.0));
+
+                let n = 50_000;
+                let mut covered = 0;
+                for seed in 0..20 {
+                    let mut hll = $name::seeded(10, seed);
+                    hll.extend(0..n);
+                    let (estimate, lower, upper) = hll.count_with_bootstrap_ci(0.95);
+                    assert_eq!(estimate, hll.estimate());
+                    assert!(lower <= estimate && estimate <= upper);
+                    assert_eq!(hll.count_with_bootstrap_ci(0.95), (estimate, lower, upper));
+                    let (_, narrow_lower, narrow_upper) = hll.count_with_bootstrap_ci(0.5);
+                    assert!(lower <= narrow_lower && narrow_upper <= upper);
+                    let width = (upper - lower) / estimate;
+                    assert!(width < 4.0 * 2.0 * error_for_precision(10));
+                    covered += (lower <= n as f64 && n as f64 <= upper) as usize;
+                }
+                assert!(covered >= 15, "{}", covered);
+            }
+
+            #[test]
+            fn test_precision_recommendation() {
+                for precision in [4, 10, 16] {
+                    let mut hll = $name::seeded(precision, $seed);
+                    assert_eq!(hll.precision_recommendation(), precision);
+                    hll.extend(0..100_000);
+                    assert_eq!(hll.precision_recommendation(), precision);
+
+                    let shift = 64 - precision as u32;
+                    for i in 0..hll.len() as u64 {
+                        hll.insert_hash((i << shift) | (1 << (shift - 1)));
+                    }
+                    assert_eq!(hll.precision_recommendation(), *PRECISION_RANGE.end());
+                }
+            }
+
+            #[test]
+            fn test_with_capacity_hint() {
+                for error in [0.5, 0.1, 0.05, 0.01, 0.003] {
+                    let hll = $name::with_capacity_hint(1_000_000, error).unwrap();
+                    assert!(error_for_precision(hll.precision()) <= error);
+                    if hll.precision() > 4 {
+                        assert!(error_for_precision(hll.precision() - 1) > error);
+                    }
+                    assert_eq!(hll.count(), 0);
+                }
+                assert_eq!($name::with_capacity_hint(1000, 0.5).unwrap().precision(), 4);
+                assert!($name::with_capacity_hint(usize::MAX, 0.01).is_err());
+                assert!($name::with_capacity_hint(1000, 0.0001).is_err());
+                assert!($name::with_capacity_hint(1000, 0.0).is_err());
+                assert!($name::with_capacity_hint(1000, f64::NAN).is_err());
+            }
+
+            #[test]
+            fn test_fill_factor() {
+                for precision in [4, 8, 12, 16] {
+                    let mut hll = $name::seeded(precision, $seed);
+                    assert_eq!(hll.fill_factor(), 0.0);
+                    let m = hll.len();
+                    hll.extend(0..m / 2);
+                    let non_zero = hll.iter().filter(|r| *r > 0).count();
+                    assert_eq!(hll.fill_factor(), non_zero as f64 / m as f64);
+                    assert!(hll.fill_factor() < 0.5);
+                    hll.extend(0..m * 4);
+                    assert!(hll.fill_factor() >= 0.5);
+                }
+            }
+
+            #[test]
+            fn test_assert_invariants() {
+                let mut hll = $name::seeded(10, $seed);
+                hll.assert_invariants();
+                hll.extend(0..10_000);
+                hll.assert_invariants();
+                hll.insert_lazy(&-1);
+                hll.assert_invariants();
+                hll.recompute_sum_and_zeros();
+                hll.assert_invariants();
+            }
+
+            #[test]
+            #[should_panic(expected = "zeros does not match the number of zero registers")]
+            fn test_assert_invariants_zeros() {
+                let mut hll = $name::seeded(10, $seed);
+                hll.insert(&1);
+                let (registers, hasher, zeros, sum, updated_count) = hll.parts();
+                let registers = registers.iter().map(|_| Default::default()).collect();
+                $name::from_parts(registers, hasher.clone(), zeros, sum, updated_count).assert_invariants();
+            }
+
+            #[test]
+            fn test_raw_components() {
+                for precision in [4, 10, 16] {
+                    for lazy in [false, true] {
+                        let mut hll = $name::seeded(precision, $seed);
+                        for x in 0..1000 {
+                            match lazy {
+                                true => hll.insert_lazy(&x),
+                                false => hll.insert(&x),
+                            }
+                        }
+                        let (zeros, sum, alpha) = hll.raw_components();
+                        assert_eq!(zeros, hll.iter().filter(|r| *r == 0).count());
+                        assert_eq!(alpha, correction(hll.len()));
+                        let m = hll.len();
+                        let d = sum + beta_correction(zeros, precision as u32);
+                        let estimate = alpha * (m * (m - zeros)) as f64 / d;
+                        assert_eq!(estimate, hll.estimate());
+                    }
+                }
+            }
+
             #[test]
-            fn test_serde() {
+            fn test_count_from_registers() {
                 for precision in 4..=18 {
-                    let mut before = $name::seeded(precision, $seed);
-                    before.extend(0..=1000);
+                    let mut hll = $name::seeded(precision, $seed);
+                    let registers: Vec<u8> = hll.iter().collect();
+                    assert_eq!($name::count_from_registers(&registers), 0.0);
+                    hll.extend(0..5000);
+                    let registers: Vec<u8> = hll.iter().collect();
+                    assert_eq!($name::count_from_registers(&registers), hll.count_from_scratch());
+                }
+                assert!($name::count_from_registers(&[]).is_nan());
+                assert!($name::count_from_registers(&[0; 8]).is_nan());
+                assert!($name::count_from_registers(&[0; 24]).is_nan());
+                assert!($name::count_from_registers(&[0; 1 << 19]).is_nan());
+                let mut registers = [0; 16];
+                registers[3] = 66;
+                assert!($name::count_from_registers(&registers).is_nan());
+                registers[3] = 65;
+                assert!(!$name::count_from_registers(&registers).is_nan());
+            }
 
-                    let s = serde_cbor::to_vec(&before).unwrap();
-                    let mut after: $name = serde_cbor::from_slice(&s).unwrap();
-                    assert_eq!(before, after);
+            #[test]
+            fn test_hasher() {
+                let mut hll = $name::seeded(10, $seed);
+                let mut control = $name::seeded(10, $seed);
+                for x in 0..1000 {
+                    hll.insert(&x);
+                    control.insert_hash(hash_one(control.hasher(), &x));
+                }
+                assert_eq!(hll, control);
+                let hasher = hll.into_hasher();
+                assert_eq!(hash_one(&hasher, &42), hash_one(control.hasher(), &42));
+            }
 
-                    before.extend(1000..=2000);
-                    after.extend(1000..=2000);
-                    assert_eq!(before, after);
+            #[test]
+            fn test_count_intersection_mle() {
+                let (mut mle_error, mut inclusion_exclusion_error) = (0.0, 0.0);
+                for seed in 0..10 {
+                    let mut a = $name::seeded(12, $seed + seed);
+                    let mut b = $name::seeded(12, $seed + seed);
+                    a.extend(0..20_000);
+                    b.extend(19_000..39_000);
+                    let both = a.count_intersection_mle(&b).unwrap();
+                    let (zeros, sum) = zeros_and_sum_of(core::iter::zip(a.iter(), b.iter()).map(|(x, y)| x.max(y)));
+                    let union = a.raw_count_inner(zeros, sum);
+                    let inclusion_exclusion = a.estimate() + b.estimate() - union;
+                    mle_error += (both - 1000.0).powi(2);
+                    inclusion_exclusion_error += (inclusion_exclusion - 1000.0).powi(2);
                 }
+                assert!(mle_error < inclusion_exclusion_error);
+                assert!(sqrt(mle_error / 10.0) < 300.0);
+
+                let mut a = $name::seeded(12, $seed);
+                let empty = $name::seeded(12, $seed);
+                assert!(a.count_intersection_mle(&empty).unwrap() < 1.0);
+                a.extend(0..10_000);
+                assert!(a.count_intersection_mle(&empty).unwrap() < 1.0);
+                let both = a.count_intersection_mle(&a).unwrap();
+                assert!((both - a.estimate()).abs() < 0.05 * a.estimate());
+                assert_eq!(
+                    a.count_intersection_mle(&$name::seeded(13, $seed)),
+                    Err(Error::IncompatibleLength)
+                );
             }
 
             #[test]
-            fn test_error_helpers() {
-                for precision in 4..=18 {
-                    let err = error_for_precision(precision);
-                    let prec = precision_for_error(err);
-                    assert_eq!(prec, precision);
+            fn test_count_intersection_three() {
+                let mut a = $name::seeded(14, $seed);
+                let mut b = $name::seeded(14, $seed);
+                let mut c = $name::seeded(14, $seed);
+                a.extend(0..60_000);
+                b.extend(30_000..90_000);
+                c.extend(40_000..100_000);
+                let a_before = a.clone();
+                let both = $name::count_intersection_three(&a, &b, &c).unwrap();
+                let err = (both - 20_000.0).abs() / 20_000.0;
+                assert!(err < 0.1, "{both}");
+                assert_eq!(a, a_before);
+
+                let mut disjoint = $name::seeded(14, $seed);
+                disjoint.extend(1_000_000..1_001_000);
+                let none = $name::count_intersection_three(&a, &b, &disjoint).unwrap();
+                assert!(none < 1000.0);
+                assert!(none >= 0.0);
+
+                assert_eq!(
+                    $name::count_intersection_three(&a, &b, &$name::seeded(9, $seed)),
+                    Err(Error::IncompatibleLength)
+                );
+            }
+
+            #[test]
+            fn test_iter_nonzero() {
+                let mut hll = $name::seeded(10, $seed);
+                assert_eq!(hll.iter_nonzero().count(), 0);
+                hll.extend(0..300);
+                let expected: Vec<_> = hll.iter().enumerate().filter(|(_, v)| *v > 0).collect();
+                assert_eq!(hll.iter_nonzero().collect::<Vec<_>>(), expected);
+                assert_eq!(expected.len(), hll.iter().filter(|v| *v > 0).count());
+            }
+
+            #[test]
+            fn test_iter_changed() {
+                let mut hll = $name::seeded(10, $seed);
+                hll.extend(0..100);
+                let baseline = hll.clone();
+                assert_eq!(hll.iter_changed(&baseline).unwrap().count(), 0);
+                hll.extend(100..300);
+                let changed: Vec<_> = hll.iter_changed(&baseline).unwrap().collect();
+                assert!(!changed.is_empty());
+                let mut patched: Vec<u8> = baseline.iter().collect();
+                for &(i, v) in changed.iter() {
+                    assert!(v > patched[i]);
+                    patched[i] = v;
                 }
+                assert!(hll.iter().eq(patched));
+                assert!(hll.iter_changed(&$name::seeded(11, $seed)).is_err());
             }
 
             #[test]
-            fn test_count_updated() {
-                for precision in 4..=18 {
+            fn test_apply_delta() {
+                for precision in [4, 10, 16] {
                     let mut hll = $name::seeded(precision, $seed);
-                    assert!(hll.updated_count());
-                    hll.insert_lazy(&42);
-                    let _ = hll.count();
-                    assert!(!hll.updated_count());
+                    hll.extend(0..100);
+                    let baseline = hll.clone();
+                    let mut replica = baseline.clone();
+                    hll.extend(100..1000);
+                    replica.apply_delta(hll.iter_changed(&baseline).unwrap()).unwrap();
+                    assert_eq!(replica, hll);
+                    assert_eq!(replica.estimate(), hll.estimate());
+                    // Smaller values do not overwrite.
+                    replica.apply_delta(baseline.iter().enumerate()).unwrap();
+                    assert_eq!(replica, hll);
+                }
+                let mut hll = $name::seeded(4, $seed);
+                assert_eq!(hll.apply_delta([(1, 3), (16, 1)]), Err(Error::IndexOutOfBounds));
+                assert_eq!(hll.apply_delta([(2, 66)]), Err(Error::InvalidEncoding));
+                assert_eq!(hll.iter_nonzero().collect::<Vec<_>>(), alloc::vec![(1, 3)]);
+            }
+
+            #[test]
+            fn test_estimate_bias() {
+                let mut hll = $name::seeded(12, $seed);
+                assert_eq!(hll.estimate_bias(), 0.0);
+                hll.extend(0..10);
+                // The raw estimate is roughly `α * m` when nearly empty.
+                assert!(hll.estimate_bias() > 100.0);
+
+                // Bias corrected with the tables, the raw estimate is close to the
+                // true count, so the relative bias matches the measured one.
+                hll.extend(10..2000);
+                let (zeros, sum) = hll.zeros_and_sum();
+                let m = hll.len() as f64;
+                let raw = hll.correction * m * m / sum;
+                let measured = (raw - 2000.0) / 2000.0;
+                assert!(zeros > 0 && raw < 5.0 * m);
+                assert!(
+                    (hll.estimate_bias() - measured).abs() < 0.1 * measured,
+                    "{}",
+                    measured
+                );
+
+                hll.extend(0..1_000_000);
+                assert_eq!(hll.estimate_bias(), 0.0);
+            }
+
+            #[test]
+            fn test_insert_many_hashes() {
+                let mut rng = fastrand::Rng::with_seed(42);
+                let hashes: Vec<u64> = (0..1000).map(|_| rng.u64(..)).collect();
+                let mut hll = $name::seeded(10, $seed);
+                let mut control = $name::seeded(10, $seed);
+                hll.insert_many_hashes(hashes.iter().copied());
+                for h in hashes {
+                    control.insert_hash(h);
+                }
+                assert_eq!(hll, control);
+                assert_eq!(hll.estimate(), control.estimate());
+            }
+
+            #[test]
+            fn test_contains_hash() {
+                let mut rng = fastrand::Rng::with_seed(42);
+                for precision in [8, 12, 16] {
+                    let mut hll = $name::seeded(precision, $seed);
+                    let m = hll.len() as f64;
+                    // About half the registers are non-zero after `m * ln(2)` inserts.
+                    let inserted: Vec<u64> = (0..(m * 0.693) as usize).map(|_| rng.u64(..)).collect();
+                    for &h in inserted.iter() {
+                        hll.insert_hash(h);
+                    }
+                    assert!(inserted.iter().all(|&h| hll.contains_hash(h)));
+
+                    let (_, sum) = hll.zeros_and_sum();
+                    let expected = 1.0 - sum / m;
+                    let fill = hll.fill_factor();
+                    assert!((fill - 0.5).abs() < 0.05, "{}", fill);
+                    assert!(expected <= fill);
+
+                    let trials = 100_000;
+                    let positives = (0..trials).filter(|_| hll.contains_hash(rng.u64(..))).count();
+                    let rate = positives as f64 / trials as f64;
+                    let std_dev = (expected * (1.0 - expected) / trials as f64).sqrt();
+                    assert!((rate - expected).abs() < 5.0 * std_dev, "{} {}", rate, expected);
+                }
+            }
+
+            #[test]
+            fn test_insert_double_hash() {
+                let mut rng = fastrand::Rng::with_seed(42);
+                let hashes: Vec<u64> = (0..1000).map(|_| rng.u64(..)).collect();
+                let mut hll = $name::seeded(10, $seed);
+                let mut control = $name::seeded(10, $seed);
+                for &h in hashes.iter() {
+                    hll.insert_double_hash(h, h);
+                    control.insert_hash(h);
+                }
+                assert_eq!(hll, control);
+                assert_eq!(hll.estimate(), control.estimate());
+
+                for precision in [4, 12, 18] {
+                    let mut wide = $name::seeded(precision, $seed);
+                    let mut pairs = $name::seeded(precision, $seed);
+                    for x in 0..100_000u64 {
+                        let (h1, h2) = (splitmix64(x), splitmix64(!x));
+                        wide.insert_128(((h1 as u128) << 64) | h2 as u128);
+                        pairs.insert_all_hashed([(h1, h2)]);
+                    }
+                    assert_eq!(wide, pairs);
+                    assert_eq!(wide.estimate(), wide.count_from_scratch());
+                    let err = (wide.estimate() - 100_000.0).abs() / 100_000.0;
+                    assert!(err < 3.0 * 1.04 / (wide.len() as f64).sqrt());
                 }
             }
         }
     };
 }
 
-impl_tests!(non_atomic, HyperLogLog, 42);
-impl_tests!(non_atomic_0_seed, HyperLogLog, 0);
-impl_tests!(atomic, AtomicHyperLogLog, 42);
+impl_tests!(non_atomic, HyperLogLog, AtomicHyperLogLog, 42);
+impl_tests!(non_atomic_0_seed, HyperLogLog, AtomicHyperLogLog, 0);
+impl_tests!(atomic, AtomicHyperLogLog, HyperLogLog, 42);
 
 #[cfg(test)]
 mod other_tests {
     use super::*;
 
+    #[test]
+    fn test_reduce() {
+        for n in [1, 2, 3, 7, 8, 33] {
+            for precision in [4, 12, 18] {
+                let sketches: Vec<_> = (0..n)
+                    .map(|i| {
+                        let mut hll = HyperLogLog::seeded(precision, 42);
+                        hll.extend(i * 1000..(i + 1) * 1000 + 500);
+                        hll
+                    })
+                    .collect();
+                let before = sketches.clone();
+                let merged = HyperLogLog::reduce(&sketches).unwrap();
+                merged.assert_invariants();
+                let mut control = HyperLogLog::seeded(precision, 42);
+                control.extend(0..n * 1000 + 500);
+                assert_eq!(merged, control);
+                assert_eq!(merged.estimate(), control.estimate());
+                assert_eq!(sketches, before);
+            }
+        }
+
+        let empty: Vec<HyperLogLog> = Vec::new();
+        assert_eq!(HyperLogLog::reduce(&empty), Err(Error::Empty));
+        let mut mixed = alloc::vec![HyperLogLog::seeded(12, 42), HyperLogLog::seeded(13, 42)];
+        mixed[1].insert(&1);
+        assert_eq!(HyperLogLog::reduce(&mixed), Err(Error::IncompatibleLength));
+    }
+
+    #[test]
+    fn test_iter_register_chunks() {
+        let mut hll = HyperLogLog::seeded(4, 42);
+        hll.extend(0..1000);
+        let registers: Vec<u8> = hll.iter().collect();
+        let chunks: Vec<&[u8; 4]> = hll.iter_register_chunks::<4>().unwrap().collect();
+        assert_eq!(chunks.len(), 4);
+        assert!(chunks
+            .iter()
+            .flat_map(|c| c.iter().copied())
+            .eq(registers.iter().copied()));
+        assert_eq!(hll.iter_register_chunks::<16>().unwrap().count(), 1);
+        assert_eq!(hll.iter_register_chunks::<1>().unwrap().count(), 16);
+        assert!(hll.iter_register_chunks::<0>().is_none());
+        assert!(hll.iter_register_chunks::<6>().is_none());
+        assert!(hll.iter_register_chunks::<32>().is_none());
+    }
+
+    #[test]
+    fn test_rotate_precision() {
+        let mut hll = HyperLogLog::seeded(12, 42);
+        hll.extend(0..200_000);
+        for precision in 4..=18 {
+            let rotated = hll.rotate_precision(precision).unwrap();
+            rotated.assert_invariants();
+            assert_eq!(rotated.precision(), precision);
+            assert_eq!(rotated.len(), 1 << precision);
+            if precision <= 12 {
+                let mut control = HyperLogLog::seeded(precision, 42);
+                control.extend(0..200_000);
+                assert_eq!(rotated, control);
+            } else {
+                let err = (rotated.estimate() - hll.estimate()).abs() / hll.estimate();
+                assert!(err < 0.05, "{precision} {}", rotated.estimate());
+            }
+        }
+        assert_eq!(hll.rotate_precision(3), Err(Error::InvalidPrecision));
+        assert_eq!(hll.rotate_precision(19), Err(Error::InvalidPrecision));
+    }
+
+    #[test]
+    fn test_merge_into() {
+        for precision in [4, 12, 18] {
+            for lazy in [false, true] {
+                let mut hll = HyperLogLog::seeded(precision, 42);
+                let mut other = HyperLogLog::seeded(precision, 42);
+                let mut control = HyperLogLog::seeded(precision, 42);
+                for x in 0..1000 {
+                    match lazy {
+                        true => hll.insert_lazy(&x),
+                        false => hll.insert(&x),
+                    }
+                }
+                other.extend(500..3000);
+                control.extend(0..3000);
+                let mut union = hll.clone();
+                union.union(&other).unwrap();
+
+                hll.merge_into(other).unwrap();
+                hll.assert_invariants();
+                assert_eq!(hll, control);
+                assert_eq!(hll, union);
+                assert_eq!(hll.count(), control.count());
+            }
+        }
+        let mut hll = HyperLogLog::seeded(12, 42);
+        assert_eq!(
+            hll.merge_into(HyperLogLog::seeded(13, 42)),
+            Err(Error::IncompatibleLength)
+        );
+    }
+
+    #[test]
+    fn test_downgrade_precision() {
+        for precision in 4..=18 {
+            for target in 4..=precision {
+                let mut hll = HyperLogLog::seeded(precision, 42);
+                hll.extend(0..2000);
+                hll.insert_lazy(&-1);
+                hll.downgrade_precision(target).unwrap();
+                hll.assert_invariants();
+
+                let mut control = HyperLogLog::seeded(target, 42);
+                control.extend(0..2000);
+                control.insert(&-1);
+                assert_eq!(hll.precision(), target);
+                assert_eq!(hll, control);
+                assert_eq!(hll.estimate(), control.estimate());
+
+                hll.extend(2000..3000);
+                control.extend(2000..3000);
+                assert_eq!(hll.estimate(), control.estimate());
+            }
+        }
+    }
+
+    #[test]
+    fn test_subsketch() {
+        let mut hll = HyperLogLog::seeded(16, 42);
+        hll.extend(0..100_000);
+        assert_eq!(hll.subsketch(0, hll.len()).unwrap(), hll);
+
+        let mut total = 0.0;
+        for i in 0..4 {
+            let sub = hll.subsketch(i << 14, (i + 1) << 14).unwrap();
+            assert_eq!(sub.precision(), 14);
+            assert_eq!(sub.estimate(), sub.count_from_scratch());
+            assert!(sub.iter().eq(hll.iter().skip(i << 14).take(1 << 14)));
+            total += sub.estimate();
+        }
+        let err = (total - hll.estimate()).abs() / hll.estimate();
+        assert!(err < 3.0 * error_for_precision(14));
+
+        assert_eq!(hll.subsketch(0, 8), Err(Error::IncompatibleLength));
+        assert_eq!(hll.subsketch(0, 100), Err(Error::IncompatibleLength));
+        assert_eq!(hll.subsketch(16, 0), Err(Error::IndexOutOfBounds));
+        assert_eq!(
+            hll.subsketch(hll.len() - 8, hll.len() + 8),
+            Err(Error::IndexOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn test_union_symmetric_difference() {
+        let mut hll = HyperLogLog::seeded(10, 42);
+        let mut other = HyperLogLog::seeded(10, 42);
+        hll.extend(0..5000);
+        other.extend(2500..7500);
+        let expected: Vec<u8> = hll.iter().zip(other.iter()).map(|(a, b)| a.max(b) - a.min(b)).collect();
+        hll.union_symmetric_difference(&other).unwrap();
+        hll.assert_invariants();
+        assert!(hll.iter().eq(expected));
+
+        let mut same = other.clone();
+        same.union_symmetric_difference(&other).unwrap();
+        same.assert_invariants();
+        assert_eq!(same, HyperLogLog::seeded(10, 42));
+        assert_eq!(
+            hll.union_symmetric_difference(&HyperLogLog::seeded(11, 42)),
+            Err(Error::IncompatibleLength)
+        );
+    }
+
+    #[test]
+    fn test_count_per_bucket() {
+        let mut hll = HyperLogLog::seeded(12, 42);
+        hll.extend(0..100_000);
+        assert_eq!(hll.count_per_bucket(1).unwrap(), [hll.estimate()]);
+        for num_buckets in [2, 16, 256] {
+            let counts = hll.count_per_bucket(num_buckets).unwrap();
+            assert_eq!(counts.len(), num_buckets);
+            let group = hll.len() / num_buckets;
+            for (i, count) in counts.into_iter().enumerate() {
+                let sub = hll.subsketch(i * group, (i + 1) * group).unwrap();
+                assert_eq!(count, sub.estimate());
+            }
+        }
+        assert_eq!(hll.count_per_bucket(0), Err(Error::IncompatibleLength));
+        assert_eq!(hll.count_per_bucket(3), Err(Error::IncompatibleLength));
+        assert_eq!(hll.count_per_bucket(512), Err(Error::IncompatibleLength));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_version() {
+        use serde_cbor::Value;
+        let mut hll = HyperLogLog::seeded(12, 42);
+        hll.extend(0..1000);
+        assert_eq!(hll.serde_version(), SERDE_VERSION);
+        let Value::Map(mut map) = serde_cbor::value::to_value(&hll).unwrap() else {
+            panic!("expected a map");
+        };
+        let key = Value::Text("serde_version".into());
+        assert_eq!(map.get(&key), Some(&Value::Integer(SERDE_VERSION as i128)));
+
+        // Serialized before the version was added.
+        map.remove(&key);
+        let bytes = serde_cbor::to_vec(&Value::Map(map.clone())).unwrap();
+        let old: HyperLogLog = serde_cbor::from_slice(&bytes).unwrap();
+        assert_eq!(old, hll);
+        let mut deserializer = serde_cbor::Deserializer::from_slice(&bytes);
+        assert_eq!(HyperLogLog::from_any_version(&mut deserializer).unwrap(), hll);
+
+        // Serialized by a future version.
+        map.insert(key, Value::Integer(SERDE_VERSION as i128 + 1));
+        let bytes = serde_cbor::to_vec(&Value::Map(map)).unwrap();
+        assert!(serde_cbor::from_slice::<HyperLogLog>(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_count_safe() {
+        let mut hll = HyperLogLog::seeded(12, 42);
+        hll.extend(0..1000);
+        assert_eq!(hll.count_safe(), hll.estimate());
+
+        let (registers, hasher, zeros, _, _) = hll.parts();
+        for (sum, expected) in [(f64::NAN, 0.0), (-1000.0, 0.0), (f64::INFINITY, 0.0)] {
+            let hll = HyperLogLog::from_parts(registers.into(), hasher.clone(), zeros, sum, true);
+            assert_eq!(hll.count_safe(), expected);
+        }
+    }
+
+    #[test]
+    fn test_from_raw_parts() {
+        for precision in 4..=18 {
+            let mut hll = HyperLogLog::seeded(precision, 42);
+            hll.extend(0..1000);
+            let (registers, hasher, zeros, sum, _) = hll.parts();
+            let raw = unsafe { HyperLogLog::from_raw_parts(precision, registers.into(), zeros, sum, hasher.clone()) };
+            raw.assert_invariants();
+            assert_eq!(raw, hll);
+            assert_eq!(raw.parts().2, hll.parts().2);
+            assert_eq!(raw.estimate(), hll.estimate());
+        }
+    }
+
+    #[test]
+    fn test_bytes_codec() {
+        for precision in 4..=18 {
+            let mut hll = HyperLogLog::seeded(precision, 42);
+            hll.extend(0..1000);
+            let bytes = hll.to_bytes();
+            assert_eq!(bytes.len(), 3 + hll.len());
+            assert_eq!(bytes[..3], [BYTES_MAGIC, BYTES_VERSION, precision]);
+            let decoded = HyperLogLog::from_bytes(&bytes, hll.parts().1.clone()).unwrap();
+            decoded.assert_invariants();
+            assert_eq!(decoded, hll);
+            assert_eq!(decoded.estimate(), hll.estimate());
+        }
+    }
+
+    #[test]
+    fn test_hex_codec() {
+        let hasher = DefaultHasher::seeded(&[0; 16]);
+        for precision in [4, 12, 18] {
+            let mut hll = HyperLogLog::with_hasher(precision, hasher.clone());
+            hll.extend(0..1000);
+            let hex = hll.to_hex();
+            assert_eq!(hex.len(), 2 * hll.to_bytes().len());
+            assert_eq!(HyperLogLog::from_hex(&hex, hasher.clone()).unwrap(), hll);
+            let upper = hex.to_uppercase();
+            assert_eq!(HyperLogLog::from_hex(&upper, hasher.clone()).unwrap(), hll);
+        }
+        let hex = HyperLogLog::with_hasher(4, hasher.clone()).to_hex();
+        assert_eq!(
+            HyperLogLog::from_hex(&hex[1..], hasher.clone()),
+            Err(Error::HexDecodeError { index: hex.len() - 1 })
+        );
+        let invalid = alloc::format!("{}g{}", &hex[..5], &hex[6..]);
+        assert_eq!(
+            HyperLogLog::from_hex(&invalid, hasher.clone()),
+            Err(Error::HexDecodeError { index: 5 })
+        );
+        assert_eq!(
+            HyperLogLog::from_hex(&hex[..hex.len() - 2], hasher),
+            Err(Error::InvalidRegisterCount { expected: 16, got: 15 })
+        );
+    }
+
+    #[test]
+    fn test_bytes_codec_invalid() {
+        let hasher = DefaultHasher::seeded(&[0; 16]);
+        let bytes = HyperLogLog::with_hasher(4, hasher.clone()).to_bytes();
+        assert_eq!(
+            HyperLogLog::from_bytes(&[], hasher.clone()),
+            Err(Error::InvalidEncoding)
+        );
+        assert_eq!(
+            HyperLogLog::from_bytes(&bytes[..2], hasher.clone()),
+            Err(Error::InvalidEncoding)
+        );
+        assert_eq!(
+            HyperLogLog::from_bytes(&bytes[..18], hasher.clone()),
+            Err(Error::InvalidRegisterCount { expected: 16, got: 15 })
+        );
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = 4;
+        assert_eq!(
+            HyperLogLog::from_bytes(&wrong_magic, hasher.clone()),
+            Err(Error::InvalidEncoding)
+        );
+        let mut wrong_version = bytes.clone();
+        wrong_version[1] = 2;
+        assert_eq!(
+            HyperLogLog::from_bytes(&wrong_version, hasher.clone()),
+            Err(Error::UnsupportedVersion { version: 2 })
+        );
+        let mut wrong_precision = bytes.clone();
+        wrong_precision[2] = 19;
+        assert_eq!(
+            HyperLogLog::from_bytes(&wrong_precision, hasher.clone()),
+            Err(Error::InvalidPrecision)
+        );
+        let mut wrong_register = bytes.clone();
+        wrong_register[3] = 66;
+        assert_eq!(
+            HyperLogLog::from_bytes(&wrong_register, hasher),
+            Err(Error::InvalidEncoding)
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json() {
+        for precision in 4..=18 {
+            let mut hll = HyperLogLog::seeded(precision, 42);
+            hll.extend(0..1000);
+            let hasher = hll.parts().1.clone();
+            let decoded = HyperLogLog::from_json(&hll.to_json(), hasher).unwrap();
+            assert_eq!(decoded, hll);
+            assert_eq!(decoded.estimate(), hll.estimate());
+        }
+    }
+
+    /// The JSON format must stay readable across crate versions.
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_stable() {
+        let json = r#"{"version":1,"precision":4,"registers":"AgMBBAABAgMABQECAQMCAQ=="}"#;
+        let hll = HyperLogLog::from_json(json, DefaultHasher::seeded(&[0; 16])).unwrap();
+        assert_eq!(
+            hll.iter().collect::<Vec<_>>(),
+            [2, 3, 1, 4, 0, 1, 2, 3, 0, 5, 1, 2, 1, 3, 2, 1]
+        );
+        assert_eq!(hll.to_json(), json);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_invalid() {
+        let hasher = DefaultHasher::seeded(&[0; 16]);
+        let from_json = |s| HyperLogLog::from_json(s, hasher.clone());
+        assert_eq!(from_json("{}"), Err(Error::InvalidEncoding));
+        let wrong_version = r#"{"version":2,"precision":4,"registers":"AAAAAAAAAAAAAAAAAAAAAA=="}"#;
+        assert_eq!(from_json(wrong_version), Err(Error::UnsupportedVersion { version: 2 }));
+        let large_version = r#"{"version":4294967295,"precision":4,"registers":"AAAAAAAAAAAAAAAAAAAAAA=="}"#;
+        assert_eq!(
+            from_json(large_version),
+            Err(Error::UnsupportedVersion { version: u32::MAX })
+        );
+        let wrong_count = r#"{"version":1,"precision":4,"registers":"AAAA"}"#;
+        assert_eq!(
+            from_json(wrong_count),
+            Err(Error::InvalidRegisterCount { expected: 16, got: 3 })
+        );
+        let not_base64 = r#"{"version":1,"precision":4,"registers":"!"}"#;
+        assert!(matches!(from_json(not_base64), Err(Error::Base64DecodeError(_))));
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn test_base64() {
+        let mut hll = HyperLogLog::seeded(12, 42);
+        hll.extend(0..1000);
+        let encoded = hll.to_base64();
+        let hasher = hll.parts().1.clone();
+        assert_eq!(HyperLogLog::from_base64(&encoded, hasher.clone()).unwrap(), hll);
+        assert!(matches!(
+            HyperLogLog::from_base64("not base64!", hasher),
+            Err(Error::Base64DecodeError(_))
+        ));
+    }
+
+    #[test]
+    fn test_recompute_sum_and_zeros() {
+        for precision in 4..=18 {
+            let mut hll = HyperLogLog::seeded(precision, 42);
+            hll.extend(0..1000);
+            let control = hll.clone();
+            hll.insert_lazy(&0);
+            assert!(!hll.updated_count());
+            hll.recompute_sum_and_zeros();
+            assert!(hll.updated_count());
+            assert_eq!(hll.estimate(), control.estimate());
+
+            let (x, y, _, _, _) = control.parts();
+            let mut hll = HyperLogLog::from_parts(x.into(), y.clone(), 0, 0.0, true);
+            hll.recompute_sum_and_zeros();
+            let (_, _, zeros, sum, updated_count) = hll.parts();
+            let (_, _, expected_zeros, expected_sum, _) = control.parts();
+            assert_eq!((zeros, sum, updated_count), (expected_zeros, expected_sum, true));
+        }
+    }
+
+    #[test]
+    fn test_downgrade_precision_invalid() {
+        let mut hll = HyperLogLog::seeded(12, 42);
+        hll.insert(&1);
+        assert_eq!(hll.downgrade_precision(13), Err(Error::InvalidPrecision));
+        assert_eq!(hll.downgrade_precision(3), Err(Error::InvalidPrecision));
+        assert_eq!(hll.precision(), 12);
+        assert_eq!(hll.count(), 1);
+    }
+
     #[test]
     fn test_parts() {
         for precision in 4..=18 {
@@ -797,28 +4267,270 @@ mod atomic_parity_tests {
             non.extend(0..=1000);
             let atomic = AtomicHyperLogLog::seeded(precision, 42);
             atomic.extend(0..=1000);
-            assert_eq!(non.raw_count(), atomic.raw_count());
+            assert_eq!(non.estimate(), atomic.estimate());
         }
     }
 
-    #[cfg(feature = "serde")]
     #[test]
-    fn serde_parity() {
+    fn test_from_atomic() {
         for precision in 4..=18 {
             let mut non = HyperLogLog::seeded(precision, 42);
             non.extend(0..=1000);
             let atomic = AtomicHyperLogLog::seeded(precision, 42);
             atomic.extend(0..=1000);
+            let converted = HyperLogLog::from(atomic);
+            assert_eq!(converted, non);
+            assert_eq!(converted.estimate(), non.estimate());
+        }
+    }
+
+    #[test]
+    fn test_try_union() {
+        for precision in [4, 12, 18] {
+            let hll = AtomicHyperLogLog::seeded(precision, 42);
+            let other = AtomicHyperLogLog::seeded(precision, 42).into_versioned();
+            hll.extend(0..500);
+            other.extend(250..1000);
+            assert_eq!(hll.try_union(&other), Ok(()));
+            let control = AtomicHyperLogLog::seeded(precision, 42);
+            control.extend(0..1000);
+            assert_eq!(hll, control);
+            assert_eq!(hll.estimate(), control.estimate());
+        }
+        let hll = AtomicHyperLogLog::seeded(12, 42);
+        assert_eq!(
+            hll.try_union(&AtomicHyperLogLog::seeded(13, 42).into_versioned()),
+            Err(Error::IncompatibleLength)
+        );
+    }
+
+    #[test]
+    fn test_load_registers_snapshot() {
+        for precision in [4, 12, 18] {
+            let hll = AtomicHyperLogLog::seeded(precision, 42);
+            assert!(hll.load_registers_snapshot().iter().all(|r| *r == 0));
+            hll.extend(0..1000);
+            let snapshot = hll.load_registers_snapshot();
+            assert_eq!(snapshot, hll.iter().collect::<Vec<_>>());
+            hll.extend(1000..2000);
+            assert_ne!(snapshot, hll.load_registers_snapshot());
+        }
+    }
+
+    #[test]
+    fn test_sync_from() {
+        for precision in [4, 12, 18] {
+            let mut baseline = HyperLogLog::seeded(precision, 42);
+            baseline.extend(0..1000);
+            let hll = AtomicHyperLogLog::seeded(precision, 42);
+            hll.extend(500..100_000);
+            hll.sync_from(&baseline).unwrap();
+            assert!(hll.iter().eq(baseline.iter()));
+            assert_eq!(hll.estimate(), baseline.estimate());
+
+            baseline.insert_lazy(&1000);
+            hll.sync_from(&baseline).unwrap();
+            assert_eq!(hll.estimate(), baseline.estimate());
+            hll.extend(1001..2000);
+            baseline.extend(1001..2000);
+            assert!(hll.iter().eq(baseline.iter()));
+            assert_eq!(hll.estimate(), baseline.estimate());
+        }
+        let hll = AtomicHyperLogLog::seeded(12, 42);
+        assert_eq!(
+            hll.sync_from(&HyperLogLog::seeded(13, 42)),
+            Err(Error::IncompatibleLength)
+        );
+    }
+
+    #[test]
+    fn test_count_atomic_f32() {
+        let mut rng = fastrand::Rng::with_seed(42);
+        for precision in 4..=18 {
+            let hll = AtomicHyperLogLog::seeded(precision, 42);
+            assert_eq!(hll.count_atomic_f32(), 0.0);
+            let mut n = 1;
+            for _ in 0..20 {
+                for _ in 0..n {
+                    hll.insert_hash(rng.u64(..));
+                }
+                n *= 2;
+                let estimate = hll.estimate();
+                let diff = (hll.count_atomic_f32() as f64 - estimate).abs() / estimate;
+                assert!(diff < 1e-6, "{} {}", precision, diff);
+                let error = hll.estimate_error_f32() as f64;
+                assert!((error / estimate - error_for_precision(precision)).abs() < 1e-6);
+            }
+            hll.insert_lazy(&0);
+            let diff = (hll.count_atomic_f32() as f64 - hll.estimate()).abs() / hll.estimate();
+            assert!(diff < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_estimate_with_ordering() {
+        let hll = AtomicHyperLogLog::seeded(12, 42);
+        let lazy = AtomicHyperLogLog::seeded(12, 42);
+        for x in 0..1000 {
+            hll.insert(&x);
+            lazy.insert_lazy(&x);
+        }
+        for ordering in [Ordering::Relaxed, Ordering::Acquire, Ordering::SeqCst] {
+            assert_eq!(hll.estimate_with_ordering(ordering), hll.estimate());
+            assert_eq!(lazy.estimate_with_ordering(ordering), lazy.estimate());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_estimate_with_release_ordering() {
+        AtomicHyperLogLog::seeded(12, 42).estimate_with_ordering(Ordering::Release);
+    }
+
+    #[test]
+    fn test_bulk_import() {
+        for precision in [4, 12, 18] {
+            let mut source = HyperLogLog::seeded(precision, 42);
+            source.extend(250..1000);
+            let hll = AtomicHyperLogLog::seeded(precision, 42);
+            hll.extend(0..500);
+            let control = hll.clone();
+            control.union_from_bytes(&source.iter().collect::<Vec<_>>()).unwrap();
+            hll.bulk_import(&source).unwrap();
+            assert_eq!(hll, control);
+            assert_eq!(hll.parts().2, control.parts().2);
+            assert!((hll.estimate() - control.estimate()).abs() < 1e-9);
+        }
+        let hll = AtomicHyperLogLog::seeded(12, 42);
+        assert_eq!(
+            hll.bulk_import(&HyperLogLog::seeded(13, 42)),
+            Err(Error::IncompatibleLength)
+        );
+    }
+
+    #[test]
+    fn test_version() {
+        let unversioned = AtomicHyperLogLog::seeded(4, 42);
+        unversioned.insert_hash(0b100);
+        assert_eq!(unversioned.version.load(Relaxed), 0);
+
+        let hll = AtomicHyperLogLog::seeded(4, 42).into_versioned();
+        assert_eq!(hll.version.load(Relaxed), 0);
+        hll.insert_hash(0b100);
+        assert_eq!(hll.version.load(Relaxed), 1);
+        hll.insert_hash(0b1);
+        assert_eq!(hll.version.load(Relaxed), 1);
+        hll.insert_hash(0b1000);
+        assert_eq!(hll.version.load(Relaxed), 2);
+    }
+
+    #[test]
+    fn test_drain_into() {
+        let atomic = alloc::sync::Arc::new(AtomicHyperLogLog::seeded(12, 42));
+        atomic.extend(0..=1000);
+        let other = alloc::sync::Arc::clone(&atomic);
+        let atomic = AtomicHyperLogLog::drain_into(atomic).unwrap_err();
+        drop(other);
+        let mut non = HyperLogLog::seeded(12, 42);
+        non.extend(0..=1000);
+        assert_eq!(AtomicHyperLogLog::drain_into(atomic).unwrap(), non);
+    }
+}
+
+#[cfg(all(feature = "rayon", not(feature = "loom")))]
+#[cfg(test)]
+mod rayon_tests {
+    use super::*;
+
+    #[test]
+    fn test_par_union_many() {
+        for precision in [4, 12, 18] {
+            let others: Vec<_> = (0..50)
+                .map(|i| {
+                    let mut other = HyperLogLog::seeded(precision, 42);
+                    other.extend(i * 1000..(i + 1) * 1000 + 500);
+                    other
+                })
+                .collect();
+
+            let hll = AtomicHyperLogLog::seeded(precision, 42);
+            hll.insert(&-1);
+            hll.par_union_many(&others).unwrap();
+
+            let control = AtomicHyperLogLog::seeded(precision, 42);
+            control.insert(&-1);
+            control.extend(0..50 * 1000 + 500);
+            assert_eq!(hll, control);
+            assert_eq!(hll.estimate(), control.estimate());
+        }
+    }
+
+    #[test]
+    fn test_recompute_sum_and_zeros_par() {
+        for precision in [4, 12, 18] {
+            let mut hll = HyperLogLog::seeded(precision, 42);
+            hll.extend(0..100_000);
+            let control = hll.clone();
+            hll.insert_lazy(&0);
+            hll.recompute_sum_and_zeros_par();
+            assert!(hll.updated_count());
+            let (_, _, zeros, sum, _) = hll.parts();
+            let (_, _, expected_zeros, expected_sum, _) = control.parts();
+            assert_eq!(zeros, expected_zeros);
+            assert!((sum - expected_sum).abs() <= expected_sum * 1e-12);
+        }
+    }
 
-            let non_bytes = serde_cbor::to_vec(&non).unwrap();
-            let atomic_bytes = serde_cbor::to_vec(&atomic).unwrap();
-            assert_eq!(non_bytes, atomic_bytes);
+    #[test]
+    fn test_insert_all_parallel() {
+        let hasher = DefaultHasher::seeded(&[42; 16]);
+        for precision in [4, 12, 18] {
+            for len in [0, 1, 7, 100_000] {
+                let data: Vec<u64> = (0..len).collect();
+                let hll = HyperLogLog::insert_all_parallel(&data, precision, hasher.clone());
+                let mut control = HyperLogLog::with_hasher(precision, hasher.clone());
+                control.extend(&data);
+                assert_eq!(hll, control);
+                assert_eq!(hll.count(), control.count());
+            }
+        }
+    }
+
+    #[test]
+    fn test_par_union_by_registers() {
+        for precision in [4, 7, 12, 18] {
+            let sources: Vec<_> = (0..50)
+                .map(|i| {
+                    let mut source = HyperLogLog::seeded(precision, 42);
+                    source.extend(i * 1000..(i + 1) * 1000 + 500);
+                    source
+                })
+                .collect();
 
-            let non_from_atomic: HyperLogLog = serde_cbor::from_slice(&atomic_bytes).unwrap();
-            let atomic_from_non: AtomicHyperLogLog = serde_cbor::from_slice(&non_bytes).unwrap();
-            assert_eq!(non_from_atomic, non);
-            assert_eq!(atomic_from_non, atomic);
+            let hll = AtomicHyperLogLog::seeded(precision, 42);
+            hll.insert(&-1);
+            hll.par_union_by_registers(&sources).unwrap();
+
+            let control = AtomicHyperLogLog::seeded(precision, 42);
+            control.insert(&-1);
+            control.extend(0..50 * 1000 + 500);
+            assert_eq!(hll, control);
+            assert_eq!(hll.estimate(), control.estimate());
         }
+        let hll = AtomicHyperLogLog::seeded(12, 42);
+        hll.par_union_by_registers(&[]).unwrap();
+        assert_eq!(hll.count(), 0);
+        let sources = [HyperLogLog::seeded(12, 42), HyperLogLog::seeded(13, 42)];
+        assert_eq!(hll.par_union_by_registers(&sources), Err(Error::IncompatibleLength));
+    }
+
+    #[test]
+    fn test_par_union_many_incompatible() {
+        let hll = AtomicHyperLogLog::seeded(12, 42);
+        hll.insert(&1);
+        let others = [HyperLogLog::seeded(12, 42), HyperLogLog::seeded(13, 42)];
+        assert_eq!(hll.par_union_many(&others), Err(Error::IncompatibleLength));
+        assert_eq!(hll.count(), 1);
     }
 }
 
@@ -850,6 +4562,54 @@ mod loom_tests {
             assert_eq!(hll.sum.load(Relaxed), expected.sum.load(Relaxed));
         });
     }
+
+    /// A writer changes register 15 and then register 0 of `other` while it is
+    /// merged, which reads register 0 first. If `try_union` succeeds, the merged
+    /// registers must be a state `other` passed through, so never register 0
+    /// without register 15.
+    #[test]
+    fn test_loom_try_union() {
+        loom::model(|| {
+            let other = loom::sync::Arc::new(AtomicHyperLogLog::seeded(4, 42).into_versioned());
+            let writer = other.clone();
+            let handle = loom::thread::spawn(move || {
+                writer.insert_hash(0xF000_0000_0000_0001);
+                writer.insert_hash(0b1);
+            });
+            let hll = AtomicHyperLogLog::seeded(4, 42);
+            let res = hll.try_union(&other);
+            handle.join().unwrap();
+            let registers: Vec<_> = hll.iter().collect();
+            if res.is_ok() {
+                assert!(registers[0] == 0 || registers[15] == 1);
+            }
+        });
+    }
+
+    /// Two threads update the same zero register. Since `fetch_max` is a single
+    /// atomic read-modify-write, only one of them observes the old value 0, so
+    /// `zeros` is decremented exactly once.
+    #[test]
+    fn test_loom_same_register() {
+        loom::model(|| {
+            let hll = loom::sync::Arc::new(AtomicHyperLogLog::seeded(4, 42));
+            // Both hashes map to register 0, with values 1 and 3.
+            let handles: Vec<_> = [0b1u64, 0b100]
+                .into_iter()
+                .map(|hash| {
+                    let v = hll.clone();
+                    loom::thread::spawn(move || v.insert_hash(hash))
+                })
+                .collect();
+
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            assert_eq!(hll.registers[0].load(Relaxed), 3);
+            assert_eq!(hll.zeros.load(Relaxed), 15);
+            assert_eq!(hll.sum.load(Relaxed), 15.0 + INV_POW2[3]);
+        });
+    }
 }
 
 #[cfg(test)]
@@ -858,9 +4618,9 @@ mod misc_tests {
 
     #[test]
     fn inv_pow_correct() {
-        for i in 0..=65 {
+        for (i, x) in INV_POW2.iter().enumerate() {
             let expected = 1.0 / ((1u128 << i) as f64);
-            assert_eq!(expected, INV_POW2[i]);
+            assert_eq!(expected, *x);
         }
     }
 }
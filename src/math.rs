@@ -22,6 +22,30 @@ pub(crate) fn pow(x: f64, y: f64) -> f64 {
     libm::pow(x, y)
 }
 
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn exp(x: f64) -> f64 {
+    x.exp()
+}
+
+#[cfg(not(feature = "std"))]
+#[inline]
+pub(crate) fn exp(x: f64) -> f64 {
+    libm::exp(x)
+}
+
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn exp_m1(x: f64) -> f64 {
+    x.exp_m1()
+}
+
+#[cfg(not(feature = "std"))]
+#[inline]
+pub(crate) fn exp_m1(x: f64) -> f64 {
+    libm::expm1(x)
+}
+
 #[cfg(feature = "std")]
 #[inline]
 pub(crate) fn ln(x: f64) -> f64 {
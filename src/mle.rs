@@ -0,0 +1,105 @@
+use crate::math::{exp, exp_m1, ln, pow};
+use crate::INV_POW2;
+
+/// Returns the maximum likelihood estimates of `[|A \ B|, |B \ A|, |A ∩ B|]`
+/// from pairs of register values `(a, b)` of two sketches `A` and `B` with
+/// the same precision and hasher, following Ertl, "New cardinality estimation
+/// algorithms for HyperLogLog sketches" (2017).
+///
+/// Under the Poisson model, register values of `A` are the max of those of
+/// `A \ B` and `A ∩ B`, and likewise for `B`, with a register of a set of
+/// cardinality `λ` being at most `k <= q` with probability
+/// `exp(-λ / m * 2^-k)`. The log-likelihood only depends on the histograms
+/// of `a` and `b` split by whether `a < b`, `a > b`, or `a = b`, and is
+/// maximized by coordinate ascent over the totals `|A|`, `|B|` and the
+/// intersection, each found by golden section search.
+pub(crate) fn joint_mle_estimate(pairs: impl Iterator<Item = (u8, u8)>, precision: u32) -> [f64; 3] {
+    use core::cmp::Ordering::{Equal, Greater, Less};
+    let q = 64 - precision as usize;
+    // Histograms of `a` and of `b` over the registers where `a < b`, and where
+    // `a > b`, and of the value of the registers where `a = b`.
+    let (mut lt, mut gt, mut eq) = ([[0usize; 66]; 2], [[0usize; 66]; 2], [0usize; 66]);
+    let mut m = 0;
+    for (a, b) in pairs {
+        let (a, b) = ((a as usize).min(q + 1), (b as usize).min(q + 1));
+        match a.cmp(&b) {
+            Less => (lt[0][a], lt[1][b]) = (lt[0][a] + 1, lt[1][b] + 1),
+            Greater => (gt[0][a], gt[1][b]) = (gt[0][a] + 1, gt[1][b] + 1),
+            Equal => eq[a] += 1,
+        }
+        m += 1;
+    }
+    let m = m as f64;
+    // With `c = λ / m`, the log of the probability that a register is at most
+    // `k`, and that it equals `k`.
+    let ln_cdf = |c: f64, k: usize| match k <= q {
+        true => -c * INV_POW2[k],
+        false => 0.0,
+    };
+    let ln_pmf = |c: f64, k: usize| match k {
+        0 => -c,
+        _ if k <= q => -c * INV_POW2[k] + ln(-exp_m1(-c * INV_POW2[k])),
+        _ => ln(-exp_m1(-c * INV_POW2[q])),
+    };
+    let log_likelihood = |only_a: f64, only_b: f64, both: f64| {
+        let (ca, cb, cx) = (only_a / m, only_b / m, both / m);
+        let mut ll = 0.0;
+        for k in 0..=q + 1 {
+            let terms = [(lt[0][k], ca + cx), (lt[1][k], cb), (gt[0][k], ca), (gt[1][k], cb + cx)];
+            for (count, c) in terms {
+                if count > 0 {
+                    ll += count as f64 * ln_pmf(c, k);
+                }
+            }
+            if eq[k] > 0 {
+                // Either the intersection sets the register, or both differences
+                // do while the intersection is below them.
+                let shared = exp(ln_pmf(cx, k) + ln_cdf(ca, k) + ln_cdf(cb, k));
+                let separate = match k {
+                    0 => 0.0,
+                    _ => exp(ln_cdf(cx, k - 1) + ln_pmf(ca, k) + ln_pmf(cb, k)),
+                };
+                ll += eq[k] as f64 * ln(shared + separate);
+            }
+        }
+        ll
+    };
+    // Maximizes `f` over `[0, hi]`, searching `ln(1 + x)` so that small values
+    // are resolved as finely as large ones.
+    let golden_section = |f: &dyn Fn(f64) -> f64, hi: f64| {
+        const INV_PHI: f64 = 0.6180339887498949;
+        let (mut lo, mut hi) = (0.0, ln(1.0 + hi));
+        let to_x = |u: f64| exp_m1(u);
+        let mut x1 = hi - INV_PHI * (hi - lo);
+        let mut x2 = lo + INV_PHI * (hi - lo);
+        let (mut f1, mut f2) = (f(to_x(x1)), f(to_x(x2)));
+        for _ in 0..60 {
+            if f1 < f2 {
+                lo = x1;
+                (x1, f1) = (x2, f2);
+                x2 = lo + INV_PHI * (hi - lo);
+                f2 = f(to_x(x2));
+            } else {
+                hi = x2;
+                (x2, f2) = (x1, f1);
+                x1 = hi - INV_PHI * (hi - lo);
+                f1 = f(to_x(x1));
+            }
+        }
+        to_x(0.5 * (lo + hi))
+    };
+    // Cardinalities above `m * 2^q` can't be told apart.
+    let max = m * pow(2.0, q as f64);
+    let (mut total_a, mut total_b, mut both) = (0.0f64, 0.0f64, 0.0f64);
+    for _ in 0..64 {
+        let previous = (total_a, total_b, both);
+        total_a = both + golden_section(&|x| log_likelihood(x, total_b - both, both), max);
+        total_b = both + golden_section(&|x| log_likelihood(total_a - both, x, both), max);
+        both = golden_section(&|x| log_likelihood(total_a - x, total_b - x, x), total_a.min(total_b));
+        let converged = |x: f64, y: f64| (x - y).abs() <= 1e-6 * x.max(1.0);
+        if converged(previous.0, total_a) && converged(previous.1, total_b) && converged(previous.2, both) {
+            break;
+        }
+    }
+    [total_a - both, total_b - both, both]
+}
@@ -0,0 +1,38 @@
+/// The version of the serialized form of [`crate::HyperLogLog`] and
+/// [`crate::AtomicHyperLogLog`] written by this version of the crate.
+///
+/// Serialized sketches without a version, i.e. from before the version was
+/// added, are version 1.
+pub const SERDE_VERSION: u8 = 1;
+
+/// Zero-sized field that serializes as [`SERDE_VERSION`], so the version is
+/// part of the serialized form without being stored in memory.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SerdeVersion;
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SerdeVersion {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u8(SERDE_VERSION)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SerdeVersion {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let version = u8::deserialize(deserializer)?;
+        match version {
+            SERDE_VERSION => Ok(SerdeVersion),
+            _ => Err(serde::de::Error::custom(format_args!(
+                "unsupported serde version {}, expected at most {}",
+                version, SERDE_VERSION
+            ))),
+        }
+    }
+}
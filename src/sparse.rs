@@ -4,9 +4,18 @@ use crate::buf::Buf;
 use crate::error::Error;
 use crate::vint::VarInt;
 use crate::DefaultHasher;
-use crate::HyperLogLog;
+use crate::{AtomicHyperLogLog, HyperLogLog};
 use alloc::vec::Vec;
 
+/// The number of bits of each hash kept by the sparse representation of
+/// [`HyperLogLogPlus`], i.e. the precision of its sparse register index.
+///
+/// In sparse mode each u64 hash is compacted into a u32: 7 bits encode the
+/// trailing zeros and the remaining 25 bits hold the register index, giving
+/// 7 + 25 = 32 bits. The sparse precision cannot be raised without widening the
+/// encoding.
+pub const SPARSE_MAX_PRECISION: u8 = 25; // u32::BITS - u64::MAX.trailing_ones() - 1;
+
 /// We compact the u64 hash into an encoded u32 for storage in the diff vec.
 /// When converting to dense representation, encoded hashes are decoded.
 /// The trailing 0s and register index of the original u64 hash are recovered
@@ -16,9 +25,9 @@ use alloc::vec::Vec;
 /// zeros equal to the full hash itself. Part of the hash is used instead of the
 /// trailing zero count to reduce collisions of encoded values, i.e. two values
 /// are more likely to share the trailing zero count than being equal.
-/// The remaining 25 bits are to encoded the register, and additional entropy
-/// from the hash.
-const MAX_PRECISION: u8 = 25; // u32::BITS - u64::MAX.trailing_ones() - 1;
+/// The remaining [`SPARSE_MAX_PRECISION`] bits are to encoded the register,
+/// and additional entropy from the hash.
+const MAX_PRECISION: u8 = SPARSE_MAX_PRECISION;
 
 #[inline]
 fn encode_hash(hash: u64) -> u32 {
@@ -36,7 +45,7 @@ fn decode_hash(hash: u32, precision: u8) -> (u32, usize) {
     let index = (hash >> (7 + MAX_PRECISION - precision)) as usize;
     match hash & 1 {
         0 => (hash.trailing_zeros(), index),
-        _ => (1 + ((hash & 0b111111) >> 1), index),
+        _ => (1 + ((hash & 0b1111111) >> 1), index),
     }
 }
 
@@ -47,11 +56,11 @@ fn correction(num: usize) -> f64 {
     // 5 extra bits of entropy.
     let buckets = (1 << (MAX_PRECISION + 5)) as f64;
     let zeros = buckets - num;
-    buckets as f64 * crate::math::ln(buckets as f64 / zeros as f64)
+    buckets * crate::math::ln(buckets / zeros)
 }
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[derive(Default, Clone, PartialEq, Eq)]
 struct DiffVec {
     encoded: Buf,
     last: u32,
@@ -59,6 +68,9 @@ struct DiffVec {
 }
 
 impl DiffVec {
+    /// The number of values shown by the `Debug` implementation.
+    const DEBUG_MAX_VALUES: usize = 32;
+
     pub fn with_size(size: usize, max_size: usize) -> Self {
         Self {
             encoded: Buf::new(size, max_size),
@@ -77,6 +89,47 @@ impl DiffVec {
         self.len as usize
     }
 
+    /// Reconstructs a `DiffVec` from its encoded bytes, as returned by
+    /// `self.encoded.as_slice()`, and the number of values encoded. Returns
+    /// `None` if the bytes are not a valid encoding of `len` strictly
+    /// increasing values.
+    fn from_encoded(bytes: &[u8], len: u32) -> Option<Self> {
+        let mut encoded = Buf::new(bytes.len() + 8, bytes.len() + 8);
+        encoded.extend_from_slice(bytes);
+        let (mut index, mut last, mut count) = (0, 0u32, 0u32);
+        while index < bytes.len() {
+            // The size of each VarInt is encoded as trailing zeros in its first byte.
+            let size = (encoded.read_u64(index) as u8).trailing_zeros() as usize + 1;
+            if size > 5 || index + size > bytes.len() {
+                return None;
+            }
+            let (diff, next) = VarInt::read(&encoded, index);
+            if diff == 0 {
+                return None;
+            }
+            last = last.checked_add(diff)?;
+            count += 1;
+            index = next;
+        }
+        if count != len {
+            return None;
+        }
+        Some(Self { encoded, last, len })
+    }
+
+    /// Returns a `DiffVec` of the values in `values`, which must be sorted.
+    /// Like [`Self::push`], repeated values are stored once.
+    #[allow(dead_code)]
+    pub fn from_sorted_slice(values: &[u32]) -> Self {
+        debug_assert!(values.windows(2).all(|w| w[0] <= w[1]), "values are not sorted");
+        let max_size = VarInt::max_encoded_bytes() * values.len() + 8;
+        let mut res = Self::with_size(max_size, max_size);
+        for &val in values {
+            res.push(val);
+        }
+        res
+    }
+
     #[inline]
     pub fn push(&mut self, val: u32) {
         if val == self.last {
@@ -97,8 +150,23 @@ impl<'a> IntoIterator for &'a DiffVec {
         DiffIter {
             index: 0,
             last: 0,
-            inner: &self,
+            inner: self,
+        }
+    }
+}
+
+/// Shows the decoded values instead of the encoded bytes, up to the first
+/// [`Self::DEBUG_MAX_VALUES`].
+impl core::fmt::Debug for DiffVec {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("DiffVec(")?;
+        let mut list = f.debug_list();
+        list.entries(self.into_iter().take(Self::DEBUG_MAX_VALUES));
+        if self.len() > Self::DEBUG_MAX_VALUES {
+            list.entry(&format_args!("..."));
         }
+        list.finish()?;
+        f.write_str(")")
     }
 }
 
@@ -129,7 +197,8 @@ impl ExactSizeIterator for DiffIter<'_> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Clone)]
+#[cfg_attr(feature = "verbose_debug", derive(Debug))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct SparseLogLog {
     /// Small temporary collection of the latset encoded hashes (u32).
@@ -149,13 +218,28 @@ impl SparseLogLog {
     const NEW_SIZE_FACTOR: usize = 25;
 
     pub fn new(precision: u8) -> Self {
+        Self::with_initial_capacity(precision, 0)
+    }
+
+    /// Returns a new `SparseLogLog` whose `new` buffer is pre-allocated to
+    /// hold `initial_cap` hashes, saving the reallocations of growing it from
+    /// empty.
+    pub fn with_initial_capacity(precision: u8, initial_cap: usize) -> Self {
         Self {
-            new: Vec::new(),
+            new: Vec::with_capacity(initial_cap),
             indexes: Default::default(),
             precision,
         }
     }
 
+    /// The initial capacity of `new` used by
+    /// [`HyperLogLogPlus::with_hasher_preallocated`]: a quarter of the number of
+    /// hashes at which `new` is flushed.
+    #[inline]
+    fn default_initial_capacity(precision: u8) -> usize {
+        Self::hll_size_bytes(precision) / Self::NEW_SIZE_FACTOR
+    }
+
     #[inline]
     fn hll_size_bytes(precision: u8) -> usize {
         (1 << precision) as usize
@@ -167,13 +251,27 @@ impl SparseLogLog {
         len * Self::NEW_SIZE_FACTOR > dense_hll_size
     }
 
+    /// Returns the number of pending (unflushed) hashes at which `new` is
+    /// flushed, i.e. the smallest length for which [`Self::should_flush`] is true.
+    #[inline]
+    pub fn flush_threshold_elements(&self) -> usize {
+        let dense_hll_size = Self::hll_size_bytes(self.precision) << 2;
+        dense_hll_size / Self::NEW_SIZE_FACTOR + 1
+    }
+
+    /// Returns the number of hashes inserted since the last flush.
+    #[inline]
+    pub fn pending_elements(&self) -> usize {
+        self.new.len()
+    }
+
     #[inline]
     pub fn insert_hash(&mut self, hash: u64) {
         let encoded = encode_hash(hash);
         if self.new.len() == self.new.capacity() {
             let dense_hll_size = ((1 << self.precision) as usize) << 2;
             let max_len = crate::math::ceil(dense_hll_size as f64 / Self::NEW_SIZE_FACTOR as f64) as usize;
-            let new_cap = core::cmp::min(1 + (3 * self.new.len()) >> 1, max_len);
+            let new_cap = core::cmp::min((1 + (3 * self.new.len())) >> 1, max_len);
             self.new.reserve_exact(new_cap - self.new.len());
         }
         self.new.push(encoded);
@@ -185,8 +283,10 @@ impl SparseLogLog {
     #[inline]
     pub(crate) fn flush_inner(&mut self, mut other: impl ExactSizeIterator<Item = u32>) {
         // TODO: empirically derive the size from the precision
+        // Start with a guess of 3 bytes per new diff; the buffer grows up to the
+        // worst case of `VarInt::max_encoded_bytes` per diff.
         let size = self.indexes.size() + (other.len() * 3);
-        let max_size = self.indexes.size() + (other.len() * 5) + 8;
+        let max_size = self.indexes.size() + (other.len() * VarInt::max_encoded_bytes()) + 8;
         let mut buf = DiffVec::with_size(size, max_size);
         let binding = core::mem::take(&mut self.indexes);
         let mut this = binding.into_iter();
@@ -228,6 +328,14 @@ impl SparseLogLog {
         self.flush_inner(new.into_iter());
     }
 
+    /// Merges already sorted encoded hashes directly into `self`, skipping the
+    /// `new` buffer and its sort.
+    #[inline]
+    pub(crate) fn insert_sorted_hashes(&mut self, sorted_hashes: &[u32]) {
+        debug_assert!(sorted_hashes.windows(2).all(|w| w[0] <= w[1]));
+        self.flush_inner(sorted_hashes.iter().copied());
+    }
+
     #[inline]
     pub(crate) fn union(&mut self, other: &Self) -> Result<(), Error> {
         if self.precision != other.precision {
@@ -235,7 +343,7 @@ impl SparseLogLog {
         }
         let mut other_new = other.new.clone();
         other_new.sort_unstable();
-        self.flush_inner(other_new.into_iter());
+        self.insert_sorted_hashes(&other_new);
         self.flush_inner(other.indexes.into_iter());
         Ok(())
     }
@@ -246,25 +354,172 @@ impl SparseLogLog {
         correction(self.indexes.len())
     }
 
+    /// Returns the same value as [`Self::count`] without flushing `self`, by
+    /// counting the hashes in `new` that are not yet stored.
+    #[cfg_attr(feature = "verbose_debug", allow(dead_code))]
+    pub fn peek_count(&self) -> f64 {
+        let mut new = self.new.clone();
+        new.sort_unstable();
+        new.dedup();
+        let mut stored = self.indexes.into_iter().peekable();
+        let unseen = new
+            .into_iter()
+            .filter(|h| {
+                while stored.next_if(|s| s < h).is_some() {}
+                stored.peek() != Some(h)
+            })
+            .count();
+        correction(self.indexes.len() + unseen)
+    }
+
+    /// Returns the number of encoded hashes stored in `self`. This is an
+    /// over-estimate of the number of distinct encoded hashes until `self` is
+    /// flushed, since `new` may contain duplicates.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.indexes.len() + self.new.len()
+    }
+
+    /// Returns `true` if no hashes have been inserted into `self`.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of bytes used by `self`, including heap allocations.
+    #[inline]
+    pub fn memory_usage_bytes(&self) -> usize {
+        core::mem::size_of::<Self>()
+            + self.new.capacity() * core::mem::size_of::<u32>()
+            + self.indexes.encoded.capacity()
+    }
+
+    /// Flushes `self` and releases unused capacity: the `new` buffer is
+    /// deallocated and the encoded hashes are shrunk to their size, e.g. to
+    /// minimize memory before `self` is kept around or serialized.
+    pub fn compact(&mut self) {
+        self.flush();
+        self.new = Vec::new();
+        self.indexes.encoded.shrink_to_fit();
+    }
+
+    /// Flushes `self` and returns its compact byte representation: 1 byte for
+    /// the precision, 4 bytes (little endian) for the number of stored hashes,
+    /// then the variable-length encoded hashes.
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_bytes(&mut self) -> Vec<u8> {
+        self.flush();
+        let encoded = self.indexes.encoded.as_slice();
+        let mut bytes = Vec::with_capacity(5 + encoded.len());
+        bytes.push(self.precision);
+        bytes.extend_from_slice(&self.indexes.len.to_le_bytes());
+        bytes.extend_from_slice(encoded);
+        bytes
+    }
+
+    /// Reconstructs a `SparseLogLog` from bytes returned by [`Self::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < 5 {
+            return Err(Error::InvalidEncoding);
+        }
+        let precision = bytes[0];
+        if !crate::PRECISION_RANGE.contains(&precision) {
+            return Err(Error::InvalidPrecision);
+        }
+        let len = u32::from_le_bytes(bytes[1..5].try_into().unwrap());
+        let indexes = DiffVec::from_encoded(&bytes[5..], len).ok_or(Error::InvalidEncoding)?;
+        Ok(Self {
+            new: Vec::new(),
+            indexes,
+            precision,
+        })
+    }
+
     #[inline]
     pub fn full(&self) -> bool {
         self.indexes.size() > Self::hll_size_bytes(self.precision)
     }
-}
 
-impl From<SparseLogLog> for HyperLogLog {
-    fn from(mut sparse: SparseLogLog) -> Self {
-        sparse.flush();
-        let registers = sparse.indexes.into_iter();
-        let mut hll = HyperLogLog::new(sparse.precision);
-        for encoded in registers {
-            let (rank, register) = decode_hash(encoded, sparse.precision);
+    /// Flushes `self` and returns an iterator over the stored hashes decoded
+    /// as `(rank, register_index)` pairs at the precision of `self`, in
+    /// ascending order of their encoded hashes. A register may appear more
+    /// than once, with different ranks.
+    pub fn iter_decoded(&mut self) -> impl Iterator<Item = (u32, usize)> + '_ {
+        self.flush();
+        let precision = self.precision;
+        self.indexes
+            .into_iter()
+            .map(move |encoded| decode_hash(encoded, precision))
+    }
+
+    /// Flushes `self` and merges its hashes into the registers of `target`,
+    /// which must have the same precision as `self`.
+    ///
+    /// The result is the same as converting `self` to a dense [`HyperLogLog`]
+    /// and merging that with [`AtomicHyperLogLog::union`], but the hashes are
+    /// decoded straight into `target`, without allocating dense registers.
+    pub fn merge_into_atomic<S: BuildHasher, const VERSIONED: bool>(
+        &mut self,
+        target: &AtomicHyperLogLog<S, VERSIONED>,
+    ) -> Result<(), Error> {
+        if target.precision() != self.precision {
+            return Err(Error::IncompatibleLength);
+        }
+        self.flush();
+        for encoded in self.indexes.into_iter() {
+            let (rank, register) = decode_hash(encoded, self.precision);
+            target.update::<true>(rank as u8, register);
+        }
+        Ok(())
+    }
+
+    /// Converts `self` into a dense [`HyperLogLog`] of the same precision.
+    ///
+    /// Register values only depend on the max rank per register, not on the
+    /// order of the hashes, so the stored and pending hashes are decoded
+    /// straight into the registers without flushing `new` into a new
+    /// `DiffVec` first.
+    pub fn into_hll(self) -> HyperLogLog {
+        let mut hll = HyperLogLog::new(self.precision);
+        let pending = self.new.iter().copied();
+        for encoded in self.indexes.into_iter().chain(pending) {
+            let (rank, register) = decode_hash(encoded, self.precision);
             hll.update::<true>(rank as u8, register);
         }
         hll
     }
 }
 
+impl PartialEq for SparseLogLog {
+    /// Two `SparseLogLog`s are equal if they contain the same set of encoded
+    /// hashes, regardless of whether they have been flushed.
+    fn eq(&self, other: &Self) -> bool {
+        if self.precision != other.precision {
+            return false;
+        }
+        if self.new.is_empty() && other.new.is_empty() {
+            return self.indexes == other.indexes;
+        }
+        let (mut this, mut other) = (self.clone(), other.clone());
+        this.flush();
+        other.flush();
+        this.indexes == other.indexes
+    }
+}
+impl Eq for SparseLogLog {}
+
+impl From<SparseLogLog> for HyperLogLog {
+    fn from(sparse: SparseLogLog) -> Self {
+        sparse.into_hll()
+    }
+}
+
+/// The first byte of [`HyperLogLogPlus::to_bytes`] for the sparse representation.
+const BYTES_SPARSE: u8 = 0;
+
+/// The first byte of [`HyperLogLogPlus::to_bytes`] for the dense representation.
+const BYTES_DENSE: u8 = 1;
+
 /// An implementation of the the [HyperLogLog++](https://static.googleusercontent.com/media/research.google.com/en//pubs/archive/40671.pdf) data structure.
 ///
 /// For small cardinalities, a "sparse" representation is used. The sparse
@@ -275,7 +530,8 @@ impl From<SparseLogLog> for HyperLogLog {
 /// switches to dense automatically. This happens inside the
 /// `insert`/`insert_hash` call (which is why it needs `&mut self`). The error
 /// of the sparse representation never exceeds that of the dense.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
+#[cfg_attr(feature = "verbose_debug", derive(Debug))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HyperLogLogPlus<S = DefaultHasher> {
     sparse: Option<SparseLogLog>,
@@ -283,6 +539,37 @@ pub struct HyperLogLogPlus<S = DefaultHasher> {
     hasher: S,
 }
 
+/// Shows a summary of `self` instead of every encoded hash. Enable the
+/// `verbose_debug` feature for the full dump.
+#[cfg(not(feature = "verbose_debug"))]
+impl core::fmt::Debug for SparseLogLog {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SparseLogLog")
+            .field("precision", &self.precision)
+            .field("num_elements", &self.len())
+            .field("size_bytes", &self.memory_usage_bytes())
+            .finish()
+    }
+}
+
+/// Shows a summary of `self` instead of its internal representation. Enable
+/// the `verbose_debug` feature for the full dump.
+#[cfg(not(feature = "verbose_debug"))]
+impl<S: BuildHasher> core::fmt::Debug for HyperLogLogPlus<S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let (phase, count) = match (self.sparse.as_ref(), self.dense.as_ref()) {
+            (Some(s), _) => ("Sparse", s.peek_count()),
+            (_, Some(d)) => ("Dense", d.estimate()),
+            _ => unreachable!(),
+        };
+        f.debug_struct("HyperLogLogPlus")
+            .field("phase", &format_args!("{}", phase))
+            .field("precision", &self.precision())
+            .field("count", &format_args!("~{}", crate::math::round(count) as usize))
+            .finish()
+    }
+}
+
 impl HyperLogLogPlus {
     /// Returns a new [`Self`] using the default hasher with a random seed.
     /// [`Self`] is initialized to use the compact and dynamically sized sparse
@@ -315,6 +602,39 @@ impl<S: BuildHasher> HyperLogLogPlus<S> {
         }
     }
 
+    /// Like [`Self::with_hasher`], but the buffer of pending sparse hashes
+    /// starts with room for `(1 << precision) / 25` hashes, a quarter of
+    /// [`Self::flush_threshold_elements`], instead of growing from empty.
+    ///
+    /// This saves about a quarter of the allocations made while `self` fills
+    /// up to the dense representation, at the cost of up to
+    /// `0.16 * (1 << precision)` bytes allocated before the first insert. It
+    /// suits sketches that are expected to fill; [`Self::with_hasher`] stays
+    /// smaller for the many small sketches the sparse representation is for.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::{DefaultHasher, HyperLogLogPlus};
+    ///
+    /// let hasher = DefaultHasher::seeded(&[42; 16]);
+    /// let mut hll = HyperLogLogPlus::with_hasher_preallocated(12, hasher.clone());
+    /// let mut control = HyperLogLogPlus::with_hasher(12, hasher);
+    /// hll.extend(0..1000);
+    /// control.extend(0..1000);
+    /// assert_eq!(hll.count(), control.count());
+    /// ```
+    pub fn with_hasher_preallocated(precision: u8, hasher: S) -> Self {
+        crate::validate_precision(precision);
+        Self {
+            sparse: Some(SparseLogLog::with_initial_capacity(
+                precision,
+                SparseLogLog::default_initial_capacity(precision),
+            )),
+            dense: None,
+            hasher,
+        }
+    }
+
     #[inline(always)]
     fn insert_sparse(this: &mut Self, h: u64) {
         this.sparse.as_mut().unwrap().insert_hash(h);
@@ -369,15 +689,157 @@ impl<S: BuildHasher> HyperLogLogPlus<S> {
     /// Returns the approximate number of elements in `self`.
     #[inline]
     pub fn count(&mut self) -> usize {
-        crate::math::round(self.raw_count()) as usize
+        crate::math::round(self.estimate()) as usize
     }
 
-    /// Returns the approximate number of elements in `self`.
+    /// Returns the approximate number of elements in `self` as a float.
+    /// [`Self::count`] is this estimate rounded to an integer.
     #[inline]
-    pub fn raw_count(&mut self) -> f64 {
+    pub fn estimate(&mut self) -> f64 {
         match self.sparse.as_mut() {
-            Some(s) => s.count() as f64,
-            _ => self.dense.as_ref().unwrap().raw_count(),
+            Some(s) => s.count(),
+            _ => self.dense.as_ref().unwrap().estimate(),
+        }
+    }
+
+    /// Returns the approximate number of elements in `self` as a float.
+    #[deprecated(note = "renamed to `estimate`")]
+    #[inline]
+    pub fn raw_count(&mut self) -> f64 {
+        self.estimate()
+    }
+
+    /// Returns the number of bytes used by the current representation of
+    /// `self`, including heap allocations.
+    ///
+    /// The sparse representation grows with the number of distinct items
+    /// inserted. Once its encoded hashes exceed `1 << precision` bytes,
+    /// `self` switches to the dense representation, which uses a fixed
+    /// `1 << precision` bytes of registers (see
+    /// [`HyperLogLog::memory_usage_bytes`]).
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLogPlus;
+    ///
+    /// let mut hll = HyperLogLogPlus::new(14);
+    /// hll.extend(0..100);
+    /// assert!(hll.current_memory_bytes() < 1 << 14);
+    ///
+    /// hll.extend(0..100_000);
+    /// assert!(hll.current_memory_bytes() > 1 << 14);
+    /// ```
+    #[inline]
+    pub fn current_memory_bytes(&self) -> usize {
+        match self.sparse.as_ref() {
+            Some(s) => s.memory_usage_bytes(),
+            _ => self.dense.as_ref().unwrap().memory_usage_bytes(),
+        }
+    }
+
+    /// Releases unused memory of the sparse representation by merging the
+    /// buffered hashes and shrinking its buffers to fit. Does nothing if `self`
+    /// is dense, since the dense representation has no spare capacity.
+    ///
+    /// Useful before keeping many sparse sketches around or serializing them.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLogPlus;
+    ///
+    /// let mut hll = HyperLogLogPlus::new(14);
+    /// hll.extend(0..100);
+    /// let before = hll.current_memory_bytes();
+    /// hll.compact();
+    /// assert!(hll.current_memory_bytes() <= before);
+    /// assert_eq!(hll.pending_elements(), Some(0));
+    /// ```
+    pub fn compact(&mut self) {
+        if let Some(sparse) = self.sparse.as_mut() {
+            sparse.compact();
+        }
+    }
+
+    /// Returns the number of items inserted since the sparse representation was
+    /// last flushed, or `None` if `self` is dense.
+    ///
+    /// In sparse mode, new hashes are buffered and periodically merged
+    /// (flushed) into the compact sorted representation. Together with
+    /// [`Self::flush_threshold_elements`], this tells how many more inserts
+    /// can be made before the next flush.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLogPlus;
+    ///
+    /// let mut hll = HyperLogLogPlus::new(12);
+    /// hll.insert(&1);
+    /// let until_flush = hll.flush_threshold_elements().unwrap() - hll.pending_elements().unwrap();
+    /// assert!(until_flush > 0);
+    /// ```
+    #[inline]
+    pub fn pending_elements(&self) -> Option<usize> {
+        self.sparse.as_ref().map(|s| s.pending_elements())
+    }
+
+    /// Returns the number of pending items at which the sparse representation is
+    /// flushed, or `None` if `self` is dense. See [`Self::pending_elements`].
+    #[inline]
+    pub fn flush_threshold_elements(&self) -> Option<usize> {
+        self.sparse.as_ref().map(|s| s.flush_threshold_elements())
+    }
+
+    /// Returns the number of encoded hashes stored by the sparse
+    /// representation, or `None` if `self` is dense. This is cheaper than
+    /// [`Self::count`] and does not need `&mut self`, but overestimates the
+    /// number of distinct encoded hashes while there are
+    /// [`Self::pending_elements`], which may contain duplicates.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLogPlus;
+    ///
+    /// let mut hll = HyperLogLogPlus::new(12);
+    /// hll.insert(&1);
+    /// hll.insert(&1);
+    /// assert_eq!(hll.sparse_len(), Some(2));
+    /// hll.compact();
+    /// assert_eq!(hll.sparse_len(), Some(1));
+    /// ```
+    #[inline]
+    pub fn sparse_len(&self) -> Option<usize> {
+        self.sparse.as_ref().map(|s| s.len())
+    }
+
+    /// Flushes the pending hashes of `self` and returns an iterator over the
+    /// stored hashes decoded as `(rank, register_index)` pairs at
+    /// [`Self::precision`], in ascending order of their encoded hashes, or
+    /// `None` if `self` is dense. A register may appear more than once, with
+    /// different ranks; its dense value is the largest of them.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLogPlus;
+    ///
+    /// let mut hll = HyperLogLogPlus::seeded(12, 42);
+    /// hll.extend(0..100);
+    /// let decoded: Vec<(u32, usize)> = hll.iter_decoded().unwrap().collect();
+    /// assert_eq!(Some(decoded.len()), hll.sparse_len());
+    /// assert!(decoded.iter().all(|(rank, index)| *rank >= 1 && *index < 1 << 12));
+    ///
+    /// hll.extend(0..10_000);
+    /// assert!(hll.iter_decoded().is_none());
+    /// ```
+    pub fn iter_decoded(&mut self) -> Option<impl Iterator<Item = (u32, usize)> + '_> {
+        self.sparse.as_mut().map(|s| s.iter_decoded())
+    }
+
+    /// Returns `true` if nothing has been inserted into `self`.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        match self.sparse.as_ref() {
+            Some(s) => s.is_empty(),
+            None => self.dense.as_ref().unwrap().iter().all(|r| r == 0),
         }
     }
 
@@ -396,6 +858,87 @@ impl<S: BuildHasher> HyperLogLogPlus<S> {
         }
     }
 
+    /// Consumes `self` and returns the inner [`HyperLogLog`] if `self` is
+    /// using the dense representation, or `None` if it is still sparse.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::HyperLogLogPlus;
+    ///
+    /// let mut hll = HyperLogLogPlus::new(4);
+    /// assert!(hll.clone().take_dense().is_none());
+    ///
+    /// hll.extend(0..1000);
+    /// assert!(hll.take_dense().is_some());
+    /// ```
+    pub fn take_dense(self) -> Option<HyperLogLog<S>> {
+        let hasher = self.hasher;
+        self.dense.map(|d| d.replace_hasher(hasher))
+    }
+
+    /// Returns `self` encoded as bytes, without serde: a byte for the
+    /// representation, then either the stored hashes of the sparse
+    /// representation or [`HyperLogLog::to_bytes`] of the dense one. Pending
+    /// sparse hashes are flushed first. The hasher is not included.
+    ///
+    /// The sparse encoding is a byte for the precision, the number of stored
+    /// hashes as 4 little endian bytes, then the variable-length encoded
+    /// hashes, so it is about as compact as `self` in memory.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::{DefaultHasher, HyperLogLogPlus};
+    ///
+    /// let hasher = DefaultHasher::seeded(&[42; 16]);
+    /// let mut before = HyperLogLogPlus::with_hasher(12, hasher.clone());
+    /// before.extend(0..100);
+    /// let bytes = before.to_bytes();
+    /// assert!(bytes.len() < 1 << 12);
+    ///
+    /// let mut after = HyperLogLogPlus::from_bytes(&bytes, hasher).unwrap();
+    /// assert_eq!(before, after);
+    /// assert_eq!(before.count(), after.count());
+    /// ```
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_bytes(&mut self) -> Vec<u8> {
+        match self.sparse.as_mut() {
+            Some(sparse) => {
+                let mut bytes = alloc::vec![BYTES_SPARSE];
+                bytes.extend_from_slice(&sparse.to_bytes());
+                bytes
+            }
+            None => {
+                let mut bytes = alloc::vec![BYTES_DENSE];
+                bytes.extend_from_slice(&self.dense.as_ref().unwrap().to_bytes());
+                bytes
+            }
+        }
+    }
+
+    /// Reconstructs a [`HyperLogLogPlus`] from bytes returned by
+    /// [`Self::to_bytes`], using `hasher`, which should be the same hasher as
+    /// the original.
+    ///
+    /// Returns `Err(Error::InvalidEncoding)` if `bytes` are not a valid
+    /// encoding, `Err(Error::InvalidPrecision)` if the precision is not in
+    /// [`crate::PRECISION_RANGE`], otherwise the same errors as
+    /// [`HyperLogLog::from_bytes`].
+    pub fn from_bytes(bytes: &[u8], hasher: S) -> Result<Self, Error> {
+        match bytes.split_first() {
+            Some((&BYTES_SPARSE, rest)) => Ok(Self {
+                sparse: Some(SparseLogLog::from_bytes(rest)?),
+                dense: None,
+                hasher,
+            }),
+            Some((&BYTES_DENSE, rest)) => Ok(Self {
+                sparse: None,
+                dense: Some(HyperLogLog::from_bytes(rest, DefaultHasher::default())?),
+                hasher,
+            }),
+            _ => Err(Error::InvalidEncoding),
+        }
+    }
+
     /// Merges another HyperLogLog into `self`, updating the count.
     /// Returns `Err(Error::IncompatibleLength)` if the two HyperLogLogs have
     /// different precision ([`Self::precision`]).
@@ -430,9 +973,107 @@ impl<S: BuildHasher> HyperLogLogPlus<S> {
     }
 }
 
+impl<S: BuildHasher> HyperLogLogPlus<S> {
+    /// Merges `self` into a shared [`AtomicHyperLogLog`], updating its count.
+    /// This is the way to combine many per-thread [`HyperLogLogPlus`]s into one
+    /// sketch: each thread merges its own while others insert into or merge
+    /// into `target`.
+    ///
+    /// If `self` is sparse, its pending hashes are flushed and every stored
+    /// hash is decoded straight into the registers of `target`, so `self` is
+    /// not switched to dense and no dense registers are allocated. If `self`
+    /// is dense, its registers are merged like [`AtomicHyperLogLog::union`].
+    ///
+    /// Returns `Err(Error::IncompatibleLength)` if the two HyperLogLogs have
+    /// different precision. This does not verify that the HLLs use equal
+    /// hashers or seeds. If they are different then `target` will be
+    /// "corrupted".
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::{AtomicHyperLogLog, HyperLogLogPlus};
+    ///
+    /// let target = AtomicHyperLogLog::seeded(12, 42);
+    /// std::thread::scope(|s| {
+    ///     for i in 0..4 {
+    ///         let target = &target;
+    ///         s.spawn(move || {
+    ///             let mut local = HyperLogLogPlus::seeded(12, 42);
+    ///             local.extend(i * 100..(i + 1) * 100);
+    ///             local.merge_into_atomic(target).unwrap();
+    ///         });
+    ///     }
+    /// });
+    ///
+    /// let control = AtomicHyperLogLog::seeded(12, 42);
+    /// control.extend(0..400);
+    /// assert_eq!(target, control);
+    /// ```
+    pub fn merge_into_atomic<const VERSIONED: bool>(
+        &mut self,
+        target: &AtomicHyperLogLog<S, VERSIONED>,
+    ) -> Result<(), Error> {
+        if self.precision() != target.precision() {
+            return Err(Error::IncompatibleLength);
+        }
+        match self.sparse.as_mut() {
+            Some(sparse) => sparse.merge_into_atomic(target),
+            None => {
+                let dense = self.dense.as_ref().unwrap();
+                for (register, value) in dense.iter().enumerate() {
+                    target.update::<true>(value, register);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<S: BuildHasher + Clone> HyperLogLogPlus<S> {
+    /// Switches `self` to the dense representation if it is sparse, and
+    /// returns a copy of the inner [`HyperLogLog`], e.g. to snapshot `self`
+    /// before further inserts.
+    ///
+    /// Switching to dense is permanent and makes `self` use `1 << precision`
+    /// bytes of registers, and the estimate of small cardinalities less
+    /// accurate. Prefer [`Clone::clone`] to snapshot `self` as is.
+    ///
+    /// # Example
+    /// ```
+    /// use hyperloglockless::{HyperLogLog, HyperLogLogPlus};
+    ///
+    /// let mut hll = HyperLogLogPlus::seeded(12, 42);
+    /// hll.extend(0..100);
+    /// let snapshot = hll.clone_dense();
+    /// assert!(!hll.is_sparse());
+    ///
+    /// let mut control = HyperLogLog::seeded(12, 42);
+    /// control.extend(0..100);
+    /// assert_eq!(snapshot, control);
+    /// ```
+    pub fn clone_dense(&mut self) -> HyperLogLog<S> {
+        if self.is_sparse() {
+            self.swap();
+        }
+        let dense = self.dense.as_ref().unwrap().clone();
+        dense.replace_hasher(self.hasher.clone())
+    }
+}
+
 impl<S: BuildHasher> PartialEq for HyperLogLogPlus<S> {
+    /// Compares the registers of two `HyperLogLogPlus`s. If one is sparse and
+    /// the other dense, the sparse one is converted to dense (in a temporary
+    /// copy) before comparing.
     fn eq(&self, other: &Self) -> bool {
-        self.sparse == other.sparse && self.dense == other.dense
+        if self.precision() != other.precision() {
+            return false;
+        }
+        match (self.sparse.as_ref(), other.sparse.as_ref()) {
+            (Some(l), Some(r)) => l == r,
+            (None, None) => self.dense == other.dense,
+            (Some(s), None) => HyperLogLog::from(s.clone()) == *other.dense.as_ref().unwrap(),
+            (None, Some(s)) => HyperLogLog::from(s.clone()) == *self.dense.as_ref().unwrap(),
+        }
     }
 }
 impl<S: BuildHasher> Eq for HyperLogLogPlus<S> {}
@@ -486,6 +1127,228 @@ mod sparse_tests {
         }
     }
 
+    /// The estimate stays accurate while inserting one element at a time,
+    /// across the switch from sparse to dense mode. Dense mode at low
+    /// precision is less accurate than 5%, so the bounds are relaxed to 3
+    /// standard errors there.
+    #[test]
+    fn test_transition_accuracy() {
+        for precision in 4..=18 {
+            let std_err = crate::error_for_precision(precision);
+            let max_err = f64::max(0.05, 3.0 * std_err);
+            let max_jump = f64::max(0.02, 3.0 * std_err);
+            for seed in 0..10 {
+                let mut hll = HyperLogLogPlus::seeded(precision, seed);
+                let (mut x, mut end, mut next_sample) = (0u64, u64::MAX, 1);
+                while x < end {
+                    let was_sparse = hll.is_sparse();
+                    hll.insert(&x);
+                    x += 1;
+                    if was_sparse && !hll.is_sparse() {
+                        let mut before = HyperLogLogPlus::seeded(precision, seed);
+                        before.extend(0..x - 1);
+                        assert!(before.is_sparse());
+                        let before = before.estimate();
+                        let jump = (hll.estimate() - before).abs() / before;
+                        assert!(jump < max_jump, "p={precision} seed={seed} jump={jump}");
+                        end = 2 * x;
+                    }
+                    // `peek_count` is O(n), so sample at 1% steps.
+                    if x >= next_sample {
+                        next_sample = x + core::cmp::max(x / 100, 1);
+                        let est = match hll.sparse.as_ref() {
+                            Some(sparse) => sparse.peek_count(),
+                            None => hll.estimate(),
+                        };
+                        let err = (est - x as f64).abs() / x as f64;
+                        assert!(err < max_err, "p={precision} seed={seed} x={x} err={err}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_take_dense() {
+        let mut sll = HyperLogLogPlus::seeded(12, 42);
+        sll.extend(0..10);
+        assert!(sll.clone().take_dense().is_none());
+
+        sll.extend(0..10000);
+        let count = sll.estimate();
+        let mut hll = sll.take_dense().unwrap();
+        assert_eq!(hll.estimate(), count);
+
+        // The hasher of the HyperLogLogPlus is carried over.
+        let mut control = HyperLogLog::seeded(12, 42);
+        control.extend(0..10000);
+        assert_eq!(hll, control);
+        hll.extend(10000..20000);
+        control.extend(10000..20000);
+        assert_eq!(hll, control);
+    }
+
+    #[test]
+    fn test_clone_dense() {
+        for precision in 4..=18 {
+            let mut hll = HyperLogLogPlus::seeded(precision, 42);
+            hll.extend(0..1000);
+            let snapshot = hll.clone_dense();
+            assert!(!hll.is_sparse());
+            assert_eq!(snapshot.estimate(), hll.estimate());
+
+            // The snapshot is independent of `hll` and uses its hasher.
+            hll.extend(1000..2000);
+            let mut control = HyperLogLog::seeded(precision, 42);
+            control.extend(0..1000);
+            assert_eq!(snapshot, control);
+            let mut snapshot = snapshot;
+            snapshot.extend(1000..2000);
+            assert_eq!(snapshot, hll.clone_dense());
+        }
+    }
+
+    /// Hashes with 32 or more trailing zeros must keep their full rank.
+    #[test]
+    fn test_high_rank_matches_dense() {
+        for precision in [4, 12, 18] {
+            for tz in 0..64 - precision as u32 {
+                let hash = (1 << 63) | (1 << tz);
+                let mut sll = SparseLogLog::new(precision);
+                sll.insert_hash(hash);
+                let mut control = HyperLogLog::new(precision);
+                control.insert_hash(hash);
+                assert_eq!(sll.into_hll(), control, "precision {} tz {}", precision, tz);
+            }
+        }
+    }
+
+    #[test]
+    fn test_merge_into_atomic() {
+        for precision in [4, 12, 18] {
+            let target = AtomicHyperLogLog::seeded(precision, 42);
+            target.extend(0..10);
+            let mut control = HyperLogLog::seeded(precision, 42);
+            control.extend(0..10);
+            for (start, num) in [(0, 0), (5, 10), (100, 1000), (2000, 50_000)] {
+                let mut hll = HyperLogLogPlus::seeded(precision, 42);
+                hll.extend(start..start + num);
+                let sparse = hll.is_sparse();
+                hll.merge_into_atomic(&target).unwrap();
+                assert_eq!(hll.is_sparse(), sparse);
+                control.extend(start..start + num);
+                assert!(target.iter().eq(control.iter()));
+                assert_eq!(target.estimate(), control.estimate());
+            }
+        }
+        let target = AtomicHyperLogLog::seeded(12, 42);
+        assert_eq!(
+            HyperLogLogPlus::seeded(13, 42).merge_into_atomic(&target),
+            Err(Error::IncompatibleLength)
+        );
+        let mut sll = SparseLogLog::new(13);
+        assert_eq!(sll.merge_into_atomic(&target), Err(Error::IncompatibleLength));
+    }
+
+    #[test]
+    fn test_sparse_len() {
+        let mut hll = HyperLogLogPlus::seeded(12, 42);
+        assert!(hll.is_empty());
+        assert_eq!(hll.sparse_len(), Some(0));
+        hll.extend([1, 2, 2]);
+        assert!(!hll.is_empty());
+        assert_eq!(hll.sparse_len(), Some(3));
+        hll.compact();
+        assert_eq!(hll.sparse_len(), Some(2));
+        hll.extend(0..10_000);
+        assert!(!hll.is_sparse());
+        assert_eq!(hll.sparse_len(), None);
+        assert!(!hll.is_empty());
+    }
+
+    #[test]
+    fn test_hll_plus_iter_decoded() {
+        let mut hll = HyperLogLogPlus::seeded(12, 42);
+        hll.extend(0..100);
+        let mut sll = hll.sparse.clone().unwrap();
+        assert!(hll.iter_decoded().unwrap().eq(sll.iter_decoded()));
+        assert_eq!(hll.pending_elements(), Some(0));
+        hll.extend(0..10_000);
+        assert!(hll.iter_decoded().is_none());
+    }
+
+    #[test]
+    fn test_into_hll() {
+        for precision in [4, 12, 18] {
+            for num in [0, 10, 1000, 5000] {
+                let mut sll = SparseLogLog::new(precision);
+                for _ in 0..num {
+                    sll.insert_hash(fastrand::u64(..));
+                }
+                let mut flushed = sll.clone();
+                flushed.flush();
+                let direct = sll.into_hll();
+                let mut control = HyperLogLog::new(precision);
+                for encoded in flushed.indexes.into_iter() {
+                    let (rank, register) = decode_hash(encoded, precision);
+                    control.update::<true>(rank as u8, register);
+                }
+                assert_eq!(direct, control);
+                assert_eq!(direct.estimate(), control.estimate());
+            }
+        }
+    }
+
+    #[test]
+    fn test_with_initial_capacity() {
+        let sll = SparseLogLog::with_initial_capacity(12, 100);
+        assert!(sll.new.capacity() >= 100);
+        assert_eq!(sll, SparseLogLog::new(12));
+
+        let mut sll = SparseLogLog::with_initial_capacity(12, 1000);
+        let mut control = SparseLogLog::new(12);
+        for _ in 0..5000 {
+            let hash = fastrand::u64(..);
+            sll.insert_hash(hash);
+            control.insert_hash(hash);
+        }
+        assert_eq!(sll, control);
+
+        for precision in [4, 12, 18] {
+            let hll = HyperLogLogPlus::new(precision);
+            assert_eq!(hll.sparse.as_ref().unwrap().new.capacity(), 0);
+
+            let hll = HyperLogLogPlus::with_hasher_preallocated(precision, DefaultHasher::default());
+            let capacity = hll.sparse.as_ref().unwrap().new.capacity();
+            assert!(capacity >= SparseLogLog::default_initial_capacity(precision));
+            assert!(capacity < SparseLogLog::new(precision).flush_threshold_elements());
+        }
+    }
+
+    #[test]
+    fn test_iter_decoded() {
+        let mut sll = SparseLogLog::new(4);
+        assert_eq!(sll.iter_decoded().count(), 0);
+        sll.insert_hash((3 << 60) | 0b100);
+        sll.insert_hash(1 << 40);
+        assert_eq!(sll.iter_decoded().collect::<Vec<_>>(), [(41, 0), (3, 3)]);
+
+        for precision in [4, 12, 18] {
+            let mut sll = SparseLogLog::new(precision);
+            for _ in 0..1000 {
+                sll.insert_hash(fastrand::u64(..));
+            }
+            let decoded: Vec<_> = sll.iter_decoded().collect();
+            assert_eq!(decoded.len(), sll.indexes.len());
+            assert!(sll.new.is_empty());
+            let mut registers = alloc::vec![0u8; 1 << precision];
+            for (rank, register) in decoded {
+                registers[register] = registers[register].max(rank as u8);
+            }
+            assert!(sll.clone().into_hll().iter().eq(registers));
+        }
+    }
+
     #[test]
     fn insert_repeat() {
         let mut sll = SparseLogLog::new(16);
@@ -497,12 +1360,280 @@ mod sparse_tests {
         assert_eq!(crate::math::round(sll.count()) as usize, 1);
     }
 
+    #[test]
+    fn test_len() {
+        let mut sll = SparseLogLog::new(12);
+        assert!(sll.is_empty());
+        assert_eq!(sll.len(), 0);
+        let hash = fastrand::u64(..);
+        sll.insert_hash(hash);
+        sll.insert_hash(hash);
+        assert!(!sll.is_empty());
+        assert_eq!(sll.len(), 2);
+        sll.flush();
+        assert_eq!(sll.len(), 1);
+        sll.insert_hash(hash);
+        assert_eq!(sll.len(), 2);
+    }
+
+    #[test]
+    fn test_flush_threshold_elements() {
+        for precision in 4..=18 {
+            let mut sll = SparseLogLog::new(precision);
+            let threshold = sll.flush_threshold_elements();
+            assert!(!SparseLogLog::should_flush(threshold - 1, precision));
+            assert!(SparseLogLog::should_flush(threshold, precision));
+            for i in 0..threshold as u64 - 1 {
+                sll.insert_hash(i << 40);
+                assert_eq!(sll.pending_elements(), i as usize + 1);
+            }
+            sll.insert_hash(u64::MAX);
+            assert_eq!(sll.pending_elements(), 0);
+        }
+
+        let mut hll = HyperLogLogPlus::seeded(12, 42);
+        assert_eq!(hll.pending_elements(), Some(0));
+        assert!(hll.flush_threshold_elements().is_some());
+        hll.swap();
+        assert_eq!(hll.pending_elements(), None);
+        assert_eq!(hll.flush_threshold_elements(), None);
+    }
+
+    #[test]
+    fn test_peek_count() {
+        let mut sll = SparseLogLog::new(12);
+        assert_eq!(sll.peek_count(), 0.0);
+        for x in 0..1000u64 {
+            // Repeats some hashes that are already stored and some in `new`.
+            sll.insert_hash(x.wrapping_mul(0x9E3779B97F4A7C15) % 0xF0000000_00000000);
+            sll.insert_hash((x / 3).wrapping_mul(0x9E3779B97F4A7C15) % 0xF0000000_00000000);
+            if x == 500 {
+                sll.flush();
+            }
+            if x % 50 == 0 {
+                assert_eq!(sll.peek_count(), sll.clone().count());
+            }
+        }
+        assert_eq!(sll.peek_count(), sll.count());
+    }
+
+    #[cfg(not(feature = "verbose_debug"))]
+    #[test]
+    fn test_diff_vec_debug() {
+        use alloc::format;
+        assert_eq!(format!("{:?}", DiffVec::default()), "DiffVec([])");
+        let short = DiffVec::from_sorted_slice(&[1, 3, 42, 256]);
+        assert_eq!(format!("{:?}", short), "DiffVec([1, 3, 42, 256])");
+        let values: Vec<u32> = (1..=100).collect();
+        let long = format!("{:?}", DiffVec::from_sorted_slice(&values));
+        let expected: Vec<_> = (1..=32).map(|x| format!("{}", x)).collect();
+        assert_eq!(long, format!("DiffVec([{}, ...])", expected.join(", ")));
+    }
+
+    #[cfg(not(feature = "verbose_debug"))]
+    #[test]
+    fn test_debug() {
+        use alloc::format;
+        let mut hll = HyperLogLogPlus::seeded(12, 42);
+        hll.insert(&1);
+        assert_eq!(
+            format!("{:?}", hll),
+            "HyperLogLogPlus { phase: Sparse, precision: 12, count: ~1 }"
+        );
+        hll.swap();
+        assert_eq!(
+            format!("{:?}", hll),
+            "HyperLogLogPlus { phase: Dense, precision: 12, count: ~1 }"
+        );
+        let mut sll = SparseLogLog::new(12);
+        sll.insert_hash(42);
+        let size = sll.memory_usage_bytes();
+        assert_eq!(
+            format!("{:?}", sll),
+            format!(
+                "SparseLogLog {{ precision: 12, num_elements: 1, size_bytes: {} }}",
+                size
+            )
+        );
+    }
+
+    #[test]
+    fn test_eq() {
+        let mut left = HyperLogLogPlus::seeded(12, 42);
+        let mut right = HyperLogLogPlus::seeded(12, 42);
+        assert_eq!(left, right);
+
+        // Same elements, different insertion order and flush state.
+        left.extend(0..100);
+        right.extend((0..100).rev());
+        right.estimate();
+        assert_eq!(left, right);
+        right.insert(&100);
+        assert!(left != right);
+
+        // Different precision.
+        assert!(HyperLogLogPlus::seeded(12, 42) != HyperLogLogPlus::seeded(13, 42));
+
+        // Mixed sparse and dense.
+        let mut sparse = HyperLogLogPlus::seeded(12, 42);
+        sparse.extend(0..100);
+        let mut dense = HyperLogLogPlus::seeded(12, 42);
+        dense.extend(0..100);
+        dense.swap();
+        assert!(sparse.is_sparse());
+        assert!(!dense.is_sparse());
+        assert_eq!(sparse, dense);
+        assert_eq!(dense, sparse);
+        dense.insert(&100);
+        assert!(sparse != dense);
+        assert!(dense != sparse);
+    }
+
+    #[test]
+    fn test_insert_sorted_hashes() {
+        let mut rng = fastrand::Rng::with_seed(42);
+        let mut sll = SparseLogLog::new(12);
+        let mut control = SparseLogLog::new(12);
+        for _ in 0..100 {
+            let hash = rng.u64(..);
+            sll.insert_hash(hash);
+            control.insert_hash(hash);
+        }
+        let hashes: Vec<u64> = (0..100).map(|_| rng.u64(..)).collect();
+        let mut encoded: Vec<u32> = hashes.iter().map(|h| encode_hash(*h)).collect();
+        encoded.sort_unstable();
+        sll.insert_sorted_hashes(&encoded);
+        for h in hashes {
+            control.insert_hash(h);
+        }
+        assert_eq!(sll, control);
+        assert_eq!(sll.count(), control.count());
+    }
+
+    #[test]
+    fn test_bytes_codec() {
+        for precision in 4..=18 {
+            for num in [0, 1, 10, 1000] {
+                let mut sll = SparseLogLog::new(precision);
+                let mut rng = fastrand::Rng::with_seed(42);
+                for _ in 0..num {
+                    sll.insert_hash(rng.u64(..));
+                }
+                let bytes = sll.to_bytes();
+                let mut decoded = SparseLogLog::from_bytes(&bytes).unwrap();
+                assert_eq!(sll, decoded);
+                assert_eq!(sll.indexes.last, decoded.indexes.last);
+                assert_eq!(sll.count(), decoded.count());
+
+                for _ in 0..num {
+                    let hash = rng.u64(..);
+                    sll.insert_hash(hash);
+                    decoded.insert_hash(hash);
+                }
+                assert_eq!(sll, decoded);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bytes_codec_invalid() {
+        let mut sll = SparseLogLog::new(12);
+        sll.insert_hash(42);
+        sll.insert_hash(1 << 60);
+        let bytes = sll.to_bytes();
+
+        assert_eq!(SparseLogLog::from_bytes(&bytes[..4]), Err(Error::InvalidEncoding));
+        assert_eq!(
+            SparseLogLog::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(Error::InvalidEncoding)
+        );
+
+        let mut wrong_precision = bytes.clone();
+        wrong_precision[0] = 19;
+        assert_eq!(SparseLogLog::from_bytes(&wrong_precision), Err(Error::InvalidPrecision));
+
+        let mut wrong_len = bytes.clone();
+        wrong_len[1] += 1;
+        assert_eq!(SparseLogLog::from_bytes(&wrong_len), Err(Error::InvalidEncoding));
+
+        let mut zero_size = bytes.clone();
+        zero_size[5] = 0;
+        assert_eq!(SparseLogLog::from_bytes(&zero_size), Err(Error::InvalidEncoding));
+    }
+
+    #[test]
+    fn test_hll_plus_bytes() {
+        for precision in [4, 12, 18] {
+            for num in [0, 1, 100, 10_000] {
+                let mut hll = HyperLogLogPlus::seeded(precision, 42);
+                hll.extend(0..num);
+                let bytes = hll.to_bytes();
+                let mut decoded = HyperLogLogPlus::from_bytes(&bytes, hll.hasher.clone()).unwrap();
+                assert_eq!(decoded.is_sparse(), hll.is_sparse());
+                assert_eq!(decoded.precision(), precision);
+                assert_eq!(decoded, hll);
+                assert_eq!(decoded.estimate(), hll.estimate());
+
+                decoded.extend(num..2 * num);
+                hll.extend(num..2 * num);
+                assert_eq!(decoded, hll);
+            }
+        }
+
+        let hasher = DefaultHasher::seeded(&[0; 16]);
+        let from_bytes = |bytes: &[u8]| HyperLogLogPlus::from_bytes(bytes, hasher.clone());
+        assert_eq!(from_bytes(&[]), Err(Error::InvalidEncoding));
+        assert_eq!(from_bytes(&[2, 12, 0, 0, 0, 0]), Err(Error::InvalidEncoding));
+        assert_eq!(
+            from_bytes(&[BYTES_SPARSE, 19, 0, 0, 0, 0]),
+            Err(Error::InvalidPrecision)
+        );
+        assert_eq!(from_bytes(&[BYTES_DENSE, 12]), Err(Error::InvalidEncoding));
+    }
+
+    #[test]
+    fn test_compact() {
+        for precision in [4, 12, 18] {
+            let mut hll = HyperLogLogPlus::seeded(precision, 42);
+            let mut control = HyperLogLogPlus::seeded(precision, 42);
+            for num in [0, 1, 10, 100, 1000] {
+                hll.extend(0..num);
+                control.extend(0..num);
+                let before = hll.current_memory_bytes();
+                hll.compact();
+                assert!(hll.current_memory_bytes() <= before);
+                assert_eq!(hll, control);
+                assert_eq!(hll.estimate(), control.estimate());
+                if let Some(sparse) = hll.sparse.as_ref() {
+                    assert_eq!(sparse.new.capacity(), 0);
+                    assert!(sparse.indexes.encoded.capacity() <= sparse.indexes.size() + 8);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_current_memory_bytes() {
+        for precision in 4..=18 {
+            let mut sll = HyperLogLogPlus::seeded(precision, 42);
+            let mut x = 0u64;
+            while sll.is_sparse() {
+                sll.insert(&x);
+                x += 1;
+            }
+            let dense = sll.current_memory_bytes();
+            assert_eq!(dense, core::mem::size_of::<HyperLogLog>() + (1 << precision));
+            sll.extend(x..x + 1000);
+            assert_eq!(sll.current_memory_bytes(), dense);
+        }
+    }
+
     #[test]
     fn test_union() {
         for seed in 0..=100 {
             let ranges = [(0, 0), (0, 1), (0, 50), (0, 2000), (0, 10000), (100, 1000)];
-            for (li, lj) in ranges.clone() {
-                for (ri, rj) in ranges.clone() {
+            for (li, lj) in ranges {
+                for (ri, rj) in ranges {
                     let mut left = HyperLogLogPlus::seeded(12, seed);
                     left.extend(li..lj);
                     let mut right = HyperLogLogPlus::seeded(12, seed);
@@ -544,6 +1675,48 @@ mod var_int_tests {
         }
     }
 
+    #[test]
+    fn diff_vec_eq() {
+        let mut left = DiffVec::with_size(100, 100);
+        let mut right = DiffVec::with_size(100, 100);
+        left.push(1);
+        right.push(2);
+        assert_eq!(left.encoded.len(), right.encoded.len());
+        assert_ne!(left, right);
+        left.push(3);
+        right.push(3);
+        assert_ne!(left, right);
+
+        let mut same = DiffVec::with_size(100, 100);
+        same.push(1);
+        same.push(3);
+        assert_eq!(left, same);
+    }
+
+    #[test]
+    fn test_from_sorted_slice() {
+        let vals = [1, 2, 2, 42, 256, 5515, 99049043, u32::MAX - 1, u32::MAX];
+        let v = DiffVec::from_sorted_slice(&vals);
+
+        let mut control = DiffVec::with_size(100, 100);
+        for x in vals.iter() {
+            control.push(*x);
+        }
+        assert_eq!(v, control);
+        assert_eq!(v.len(), vals.len() - 1);
+        let mut expected = vals.to_vec();
+        expected.dedup();
+        assert_eq!(v.into_iter().collect::<Vec<_>>(), expected);
+        assert_eq!(DiffVec::from_sorted_slice(&[]).len(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn test_from_sorted_slice_unsorted() {
+        DiffVec::from_sorted_slice(&[2, 1]);
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn test_serde_sparse() {
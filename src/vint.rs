@@ -1,8 +1,16 @@
 use crate::buf::Buf;
+use alloc::vec::Vec;
 
 pub struct VarInt;
 
 impl VarInt {
+    /// Returns the largest number of bytes [`Self::write`] uses for any `u32`,
+    /// which is the size of `u32::MAX`: 32 value bits plus 5 size bits.
+    #[inline]
+    pub const fn max_encoded_bytes() -> usize {
+        5
+    }
+
     #[inline]
     pub fn write(buf: &mut Buf, val: u32) {
         let x = val as u64;
@@ -19,6 +27,23 @@ impl VarInt {
         let res = (x & mask) >> size;
         (res as u32, index + size)
     }
+
+    /// Decodes every value written to `buf` as differences from the previous
+    /// value, as in `DiffVec`, returning the absolute values: the running sum
+    /// of the decoded values.
+    #[allow(dead_code)]
+    pub fn decode_all(buf: &Buf) -> Vec<u32> {
+        // Every value takes at least one byte.
+        let mut res = Vec::with_capacity(buf.len());
+        let (mut index, mut last) = (0, 0u32);
+        while index < buf.len() {
+            let (diff, next) = Self::read(buf, index);
+            last += diff;
+            res.push(last);
+            index = next;
+        }
+        res
+    }
 }
 
 #[cfg(test)]
@@ -44,6 +69,37 @@ mod tests {
         assert_eq!(decoded_second, 256);
     }
 
+    #[test]
+    fn max_encoded_bytes() {
+        let mut buf = Buf::new(100, 100);
+        VarInt::write(&mut buf, u32::MAX);
+        assert_eq!(buf.len(), VarInt::max_encoded_bytes());
+        for val in [0, 1, 127, 128, 1 << 14, 1 << 21, 1 << 28, u32::MAX - 1] {
+            let mut buf = Buf::new(100, 100);
+            VarInt::write(&mut buf, val);
+            assert!(buf.len() <= VarInt::max_encoded_bytes());
+        }
+    }
+
+    #[test]
+    fn decode_all() {
+        let mut buf = Buf::new(100, 100);
+        assert!(VarInt::decode_all(&buf).is_empty());
+        let diffs = [1, 0, 127, 128, 5515, 99049043, 3];
+        for diff in diffs {
+            VarInt::write(&mut buf, diff);
+        }
+        let decoded = VarInt::decode_all(&buf);
+        let expected: Vec<_> = diffs
+            .iter()
+            .scan(0, |sum, diff| {
+                *sum += diff;
+                Some(*sum)
+            })
+            .collect();
+        assert_eq!(decoded, expected);
+    }
+
     #[test]
     fn codec_perm() {
         let mut buf = Buf::new(100, 100);